@@ -1,155 +1,1050 @@
-use crate::bigint::{BigInt, ONE, THREE, TWO};
+use crate::bigint::{
+    add_with_carry, mul_add, sub_with_borrow, BigInt, SignedBigInt, ONE, THREE, TWO, ZERO,
+};
+use crate::modint::{ModInt, Modulus};
+use crate::trace::{self, Tracer};
 
-use once_cell::sync::Lazy;
+use std::collections::HashMap;
 
-/// 巴雷特模乘，需要确保 x < mod_num^2
-pub fn barrett_mod(x: &BigInt, m: &BigInt, mod_num: &BigInt) -> BigInt {
-    if mod_num.is_zero() || x < mod_num {
-        return x.clone();
+/// 巴雷特约简的预计算结果：m = floor(2^shift / n)，shift 为 n 位长的两倍。
+/// 对同一模数反复约简时只需构造一次
+#[derive(Clone)]
+pub struct BarrettContext {
+    n: BigInt,
+    m: BigInt,
+    shift: usize,
+}
+
+impl BarrettContext {
+    /// n 为 0 时 panic
+    pub fn new(n: &BigInt) -> Self {
+        let shift = 2 * n.bit_length();
+        Self {
+            n: n.clone(),
+            m: &(&ONE << shift) / n,
+            shift,
+        }
+    }
+
+    pub fn n(&self) -> &BigInt {
+        &self.n
+    }
+
+    /// x mod n，需要确保 x < n^2
+    pub fn reduce(&self, x: &BigInt) -> BigInt {
+        let mut res = BigInt::with_capacity(1);
+        let (mut q, mut t) = (BigInt::with_capacity(1), BigInt::with_capacity(1));
+        self.reduce_into(x, &mut q, &mut t, &mut res);
+        res
+    }
+
+    /// 同 `reduce`，结果写入 out，q 与 t 为可反复使用的临时空间
+    fn reduce_into(&self, x: &BigInt, q: &mut BigInt, t: &mut BigInt, out: &mut BigInt) {
+        if *x < self.n {
+            out.copy_from(x);
+            return;
+        }
+
+        x.mul_into(&self.m, q);
+        *q >>= self.shift;
+        q.mul_into(&self.n, t);
+        x.sub_into(t, out);
+        while *out >= self.n {
+            *out -= &self.n;
+        }
     }
 
-    let k = 2 * mod_num.bitlen() as u64;
-    let tmp = &(x * m) >> k;
-    let mut res = x - &(&tmp * mod_num);
-    while res >= *mod_num {
-        res = &res - mod_num;
+    /// a^b mod n，a < n
+    pub fn mod_pow(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        self.mod_pow_traced(a, b, None)
     }
-    res
+
+    /// 同 `mod_pow`，并记录从高到低每一位上的平方与乘法步骤
+    pub fn mod_pow_traced(
+        &self,
+        a: &BigInt,
+        b: &BigInt,
+        mut trace: Option<&mut dyn Tracer>,
+    ) -> BigInt {
+        // 循环中的乘积与约简都写入预先分配的空间
+        let cap = 2 * self.n.len() + 2;
+        let mut res = BigInt::with_capacity(cap);
+        res.set_bit(0, true);
+        let mut prod = BigInt::with_capacity(cap);
+        let mut q = BigInt::with_capacity(2 * cap);
+        let mut t = BigInt::with_capacity(2 * cap);
+
+        // b 为 0 时也做一次约简，使模数为 1 时结果为 0
+        for bit in (0..b.bit_length().max(1)).rev() {
+            res.square_into(&mut prod);
+            self.reduce_into(&prod, &mut q, &mut t, &mut res);
+            trace::record(&mut trace, || {
+                (format!("bit {}: square", bit), res.fmt_hex())
+            });
+            if b.bit(bit) {
+                res.mul_into(a, &mut prod);
+                self.reduce_into(&prod, &mut q, &mut t, &mut res);
+                trace::record(&mut trace, || {
+                    (format!("bit {}: multiply", bit), res.fmt_hex())
+                });
+            }
+        }
+        res
+    }
+}
+
+/// 蒙哥马利模乘的预计算结果，R = 2^(VALUE_LEN * n.len())。
+/// 对同一模数反复做幂运算时，每次约简只需乘加与移位，不需要巴雷特约简中的大数乘法
+#[derive(Clone)]
+pub struct MontgomeryContext {
+    n: BigInt,
+    /// -n^-1 mod 2^VALUE_LEN
+    n_prime: u64,
+    /// R mod n，即蒙哥马利形式下的 1
+    r: BigInt,
+    /// R^2 mod n，用于转入蒙哥马利形式
+    r2: BigInt,
 }
 
-pub fn mod_power(a: &BigInt, b: &BigInt, barrett_m: &BigInt, mod_num: &BigInt) -> BigInt {
-    let mut res = BigInt::with_capacity(2);
-    res.length = 1;
-    res.value[0] = 1;
+impl MontgomeryContext {
+    pub fn new(n: &BigInt) -> Result<Self, &'static str> {
+        let n0 = n.limbs()[0];
+        if n0 & 1 == 0 {
+            return Err("Montgomery modulus must be odd");
+        }
+        // 牛顿迭代，每轮正确的位数翻倍：1 -> 2 -> 4 -> ... -> 64
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(n0.wrapping_mul(inv)));
+        }
+        let n_prime = inv.wrapping_neg();
+        let r_bits = BigInt::VALUE_LEN * n.len() as u64;
+        Ok(Self {
+            n: n.clone(),
+            n_prime,
+            r: &(&ONE << r_bits) % n,
+            r2: &(&ONE << (2 * r_bits)) % n,
+        })
+    }
+
+    /// 返回 a * b * R^-1 mod n，要求 a, b < n
+    pub fn mont_mul(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        BigInt::from_slice(&self.mont_mul_fixed(&self.fixed(a), &self.fixed(b)))
+    }
+
+    /// x 补 0 到恰好 n.len() 个 value，要求 x < n
+    fn fixed(&self, x: &BigInt) -> Vec<u64> {
+        let mut v = x.limbs().to_vec();
+        v.resize(self.n.len(), 0);
+        v
+    }
+
+    /// 定长的蒙哥马利乘法：a、b 与结果都恰好是 n.len() 个 value，不去掉高位的 0。
+    /// 最后总是算出 t - n，再按借位以掩码选取 t 或 t - n，不论数据如何执行的运算都相同
+    fn mont_mul_fixed(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = self.n.limbs();
+        let s = n.len();
+        let mut t = vec![0; s + 2];
+        for &ai in &a[..s] {
+            // t += a_i * b
+            let mut carry = 0;
+            for (tj, &bj) in t[..s].iter_mut().zip(b) {
+                (*tj, carry) = mul_add(ai, bj, *tj, carry);
+            }
+            (t[s], t[s + 1]) = add_with_carry(t[s], carry, 0);
+
+            // t = (t + m * n) / 2^VALUE_LEN，m 使最低位恰好为 0
+            let m = t[0].wrapping_mul(self.n_prime);
+            let (_, mut carry) = mul_add(m, n[0], t[0], 0);
+            for j in 1..s {
+                (t[j - 1], carry) = mul_add(m, n[j], t[j], carry);
+            }
+            let c;
+            (t[s - 1], c) = add_with_carry(t[s], carry, 0);
+            t[s] = t[s + 1] + c;
+            t[s + 1] = 0;
+        }
+
+        // 此时 t < 2n。t < n 时 t - n 最终借位，mask 全为 1，保留 t
+        let mut d = vec![0; s];
+        let mut borrow = 0;
+        for (dj, (&tj, &nj)) in d.iter_mut().zip(t.iter().zip(n)) {
+            (*dj, borrow) = sub_with_borrow(tj, nj, borrow);
+        }
+        let (_, borrow) = sub_with_borrow(t[s], 0, borrow);
+        let mask = borrow.wrapping_neg();
+        t.truncate(s);
+        for (tj, dj) in t.iter_mut().zip(d) {
+            *tj = (*tj & mask) | (dj & !mask);
+        }
+        t
+    }
 
-    for i in (0..b.length).rev() {
-        let mut max_bit = BigInt::VALUE_LEN - 1;
-        if i == b.length - 1 {
-            while (1 << max_bit) & b.value[i] == 0 {
-                max_bit -= 1;
+    /// 返回 a^2 * R^-1 mod n，要求 a < n。先用 `BigInt::square` 求平方，再逐位约简
+    pub fn mont_square(&self, a: &BigInt) -> BigInt {
+        let n = self.n.limbs();
+        let s = n.len();
+        let mut t = a.square().limbs().to_vec();
+        t.resize(2 * s + 2, 0);
+        for i in 0..s {
+            // 加上 m * n * 2^(VALUE_LEN * i)，使第 i 位为 0
+            let m = t[i].wrapping_mul(self.n_prime);
+            let mut carry = 0;
+            for j in 0..s {
+                (t[i + j], carry) = mul_add(m, n[j], t[i + j], carry);
             }
+            let mut k = i + s;
+            while carry != 0 {
+                (t[k], carry) = add_with_carry(t[k], carry, 0);
+                k += 1;
+            }
+        }
+
+        // 此时 t / R < 2n
+        let mut res = BigInt::from_slice(&t[s..=2 * s]);
+        if let Some(r) = res.checked_sub(&self.n) {
+            res = r;
         }
+        res
+    }
 
-        for j in (0..=max_bit).rev() {
-            res = barrett_mod(&(&res * &res), barrett_m, mod_num);
-            if (1 << j) & b.value[i] != 0 {
-                res = barrett_mod(&(&res * a), barrett_m, mod_num);
+    pub fn to_mont(&self, a: &BigInt) -> BigInt {
+        let a = if *a >= self.n { a % &self.n } else { a.clone() };
+        self.mont_mul(&a, &self.r2)
+    }
+
+    /// `to_mont` 的逆过程，由蒙哥马利形式还原为普通形式
+    pub fn leave_mont(&self, a: &BigInt) -> BigInt {
+        self.mont_mul(a, &ONE)
+    }
+
+    /// a^b mod n，过程同 `mod_power`，但全程在蒙哥马利形式下计算
+    pub fn mod_power(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        let a = self.to_mont(a);
+        let mut res = self.r.clone();
+        for bit in b.windows(1) {
+            res = self.mont_square(&res);
+            if bit == 1 {
+                res = self.mont_mul(&res, &a);
             }
         }
+        self.leave_mont(&res)
+    }
+
+    /// a^b mod n，蒙哥马利阶梯：不论 b 的各位如何，每一位都做两次定长的蒙哥马利乘法，
+    /// 以常数时间的交换代替分支，且总是扫描与 n 同样多的位。中间结果始终是 n.len() 个 value，
+    /// 乘法最后的减法也以掩码代替分支，私钥指数的位模式不会从耗时中泄露
+    pub fn mod_power_ct(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        // 保持 r1 = r0 * a
+        let mut r0 = self.fixed(&self.r);
+        let mut r1 = self.fixed(&self.to_mont(a));
+        for i in (0..self.n.bit_length().max(b.bit_length())).rev() {
+            let bit = b.bit(i);
+            // 位为 1 时交换后计算，效果为 r0 = r0 * r1, r1 = r1^2
+            ct_swap_limbs(&mut r0, &mut r1, bit);
+            r1 = self.mont_mul_fixed(&r0, &r1);
+            r0 = self.mont_mul_fixed(&r0, &r0);
+            ct_swap_limbs(&mut r0, &mut r1, bit);
+        }
+        let mut one = vec![0; self.n.len()];
+        one[0] = 1;
+        BigInt::from_slice(&self.mont_mul_fixed(&r0, &one))
     }
-    res
 }
 
-/// 扩展模 `mod_num` 欧几里得算法，返回 `(gcd, u, v)`, `d = ua + vb`
-pub fn extended_euclid(
-    a: u64,
-    b: u64,
-    barrett_m: &BigInt,
-    mod_num: &BigInt,
-) -> (u64, BigInt, BigInt) {
-    if b != 0 {
-        let q = a / b;
-        let r = a % b;
-        let (d, mut u, v) = extended_euclid(b, r, barrett_m, mod_num);
-        let qv = barrett_mod(&(&v * q), barrett_m, mod_num);
-        if u < qv {
-            u = &u + &mod_num;
+/// choice 为 true 时交换两个等长的 value 数组，不论 choice 如何执行的运算都相同
+fn ct_swap_limbs(a: &mut [u64], b: &mut [u64], choice: bool) {
+    let mask = (choice as u64).wrapping_neg();
+    for (x, y) in a.iter_mut().zip(b.iter_mut()) {
+        let t = (*x ^ *y) & mask;
+        *x ^= t;
+        *y ^= t;
+    }
+}
+
+/// 固定窗口模幂的预计算表：底数 a 在蒙哥马利形式下的 a^0 到 a^(2^window - 1)。
+/// 同一底数可反复用于不同的指数，模数相同的各次幂运算共用同一个 `MontgomeryContext`
+pub struct PowerTable {
+    window: usize,
+    powers: Vec<BigInt>,
+}
+
+impl PowerTable {
+    pub fn new(ctx: &MontgomeryContext, a: &BigInt, window: usize) -> Self {
+        assert!(
+            (1..=8).contains(&window),
+            "window size must be between 1 and 8"
+        );
+        let a = ctx.to_mont(a);
+        let mut powers = Vec::with_capacity(1 << window);
+        powers.push(ctx.r.clone());
+        for i in 1..1 << window {
+            powers.push(ctx.mont_mul(&powers[i - 1], &a));
         }
-        (d, v, barrett_mod(&(&u - &qv), barrett_m, mod_num))
+        Self { window, powers }
+    }
+
+    /// 按指数位数选取窗口宽度，使建表与查表相乘的总次数大致最少
+    pub fn window_for(exp_bits: usize) -> usize {
+        match exp_bits {
+            0..=24 => 2,
+            25..=80 => 3,
+            81..=240 => 4,
+            241..=672 => 5,
+            _ => 6,
+        }
+    }
+}
+
+/// a^b mod n，a 由 table 给出。每个窗口做 window 次平方，窗口不为 0 时再乘上表中的一项
+pub fn mod_power_with_table(ctx: &MontgomeryContext, table: &PowerTable, b: &BigInt) -> BigInt {
+    let mut res = ctx.r.clone();
+    for digit in b.windows(table.window) {
+        for _ in 0..table.window {
+            res = ctx.mont_square(&res);
+        }
+        if digit != 0 {
+            res = ctx.mont_mul(&res, &table.powers[digit as usize]);
+        }
+    }
+    ctx.leave_mont(&res)
+}
+
+/// 扩展欧几里得算法，返回 `(gcd, u, v)`, `gcd = ua + vb`，u 与 v 可能为负
+pub fn extended_euclid(a: &BigInt, b: &BigInt) -> (BigInt, SignedBigInt, SignedBigInt) {
+    // 始终保持 r_i = u_i * a + v_i * b
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut u0, mut u1) = (
+        SignedBigInt::from(ONE.clone()),
+        SignedBigInt::from(ZERO.clone()),
+    );
+    let (mut v0, mut v1) = (
+        SignedBigInt::from(ZERO.clone()),
+        SignedBigInt::from(ONE.clone()),
+    );
+    while !r1.is_zero() {
+        let (q, r) = r0.div_rem(&r1);
+        let q = SignedBigInt::from(q);
+        let u = &u0 - &(&q * &u1);
+        let v = &v0 - &(&q * &v1);
+        (r0, r1) = (r1, r);
+        (u0, u1) = (u1, u);
+        (v0, v1) = (v1, v);
+    }
+    (r0, u0, v0)
+}
+
+/// Lehmer 最大公约数：只用 a 最高的 64 位与 b 的对应位模拟若干步欧几里得算法，
+/// 得到的商都正确时把这些步骤合并成一次 a, b 的线性组合，每轮只需做两次标量乘法
+pub fn lehmer_gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    let (mut a, mut b) = if a >= b {
+        (a.clone(), b.clone())
     } else {
-        (a, BigInt::from_slice(&[1]), BigInt::from_slice(&[0]))
+        (b.clone(), a.clone())
+    };
+    while b.len() > 1 {
+        let shift = a.bit_length() - BigInt::VALUE_LEN as usize;
+        let mut x = (&a >> shift).limbs()[0] as i128;
+        let mut y = (&b >> shift).limbs()[0] as i128;
+        // Knuth 算法 L，合并后新的 a = ca * a + cb * b，b = cc * a + cd * b
+        let (mut ca, mut cb, mut cc, mut cd) = (1i128, 0i128, 0i128, 1i128);
+        while y + cc != 0 && y + cd != 0 {
+            let q = (x + ca) / (y + cc);
+            if q != (x + cb) / (y + cd) {
+                break;
+            }
+            (ca, cc) = (cc, ca - q * cc);
+            (cb, cd) = (cd, cb - q * cd);
+            (x, y) = (y, x - q * y);
+        }
+        if cb == 0 {
+            // 第一步的商就无法确定，做一次完整的除法
+            let r = &a % &b;
+            (a, b) = (b, r);
+        } else {
+            (a, b) = (combine(&a, &b, ca, cb), combine(&a, &b, cc, cd));
+        }
     }
+
+    // b 只剩一个 value，余下的在 u64 上完成
+    let b = b.limbs()[0];
+    if b == 0 {
+        return a;
+    }
+    let (mut x, mut y) = (b, a.rem_u64(b));
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    BigInt::from(x)
 }
 
-fn is_prime(n: u64) -> bool {
-    if n < 2 {
-        return false;
+/// 欧几里得算法，每一步做一次大数除法
+pub fn euclid_gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while !b.is_zero() {
+        let r = &a % &b;
+        (a, b) = (b, r);
     }
-    for i in 2..=n / 2 {
-        if n % i == 0 {
-            return false;
+    a
+}
+
+/// 二进制（Stein）最大公约数：只用移位与减法，不做大数除法
+pub fn binary_gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    if a.is_zero() {
+        return b.clone();
+    }
+    if b.is_zero() {
+        return a.clone();
+    }
+    // gcd(2^i * a', 2^j * b') = 2^min(i, j) * gcd(a', b')
+    let shift = a.trailing_zeros().min(b.trailing_zeros());
+    let mut a = a >> a.trailing_zeros();
+    let mut b = b.clone();
+    loop {
+        // a 始终为奇数，b 的因子 2 可以直接去掉
+        b >>= b.trailing_zeros();
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        b -= &a;
+        if b.is_zero() {
+            return &a << shift;
         }
     }
-    true
 }
-fn small_primes() -> [u64; 1229] {
-    let mut arr = [0; 1229];
+
+/// s * a + t * b，s 与 t 不同为正也不同为负，且结果非负
+fn combine(a: &BigInt, b: &BigInt, s: i128, t: i128) -> BigInt {
+    if t <= 0 {
+        &a.mul_u64(s as u64) - &b.mul_u64(t.unsigned_abs() as u64)
+    } else {
+        &b.mul_u64(t as u64) - &a.mul_u64(s.unsigned_abs() as u64)
+    }
+}
+
+/// `SMALL_PRIMES` 的上界
+const SMALL_PRIME_LIMIT: usize = 10000;
+
+/// 10000 以下素数的个数
+pub const SMALL_PRIME_COUNT: usize = 1229;
+
+/// 埃拉托斯特尼筛，在编译期求出 `SMALL_PRIME_LIMIT` 以下的全部素数
+const fn small_primes() -> [u64; SMALL_PRIME_COUNT] {
+    let mut composite = [false; SMALL_PRIME_LIMIT];
+    let mut arr = [0; SMALL_PRIME_COUNT];
     let mut i = 0;
-    for n in 2..10000 {
-        if is_prime(n) {
-            arr[i] = n;
+    let mut n = 2;
+    while n < SMALL_PRIME_LIMIT {
+        if !composite[n] {
+            arr[i] = n as u64;
             i += 1;
+            let mut m = n * n;
+            while m < SMALL_PRIME_LIMIT {
+                composite[m] = true;
+                m += n;
+            }
         }
+        n += 1;
     }
+    assert!(i == SMALL_PRIME_COUNT);
     arr
 }
 
-pub fn miller_rabin(n: &BigInt) -> bool {
-    const MR_TEST_TIMES: usize = 64;
-    static SMALL_PRIMES: Lazy<[u64; 1229]> = Lazy::new(small_primes);
+/// 10000 以下的全部素数，供试除与筛法使用
+pub static SMALL_PRIMES: [u64; SMALL_PRIME_COUNT] = small_primes();
 
-    // shortcuts
-    if n == Lazy::force(&TWO) || n == Lazy::force(&THREE) {
-        return true;
+/// 将 `SMALL_PRIMES` 按顺序分组，每组的乘积不超过 u64 时的组数
+const fn small_prime_group_count() -> usize {
+    let mut count = 1;
+    let mut product: u64 = 1;
+    let mut i = 0;
+    while i < SMALL_PRIME_COUNT {
+        match product.checked_mul(SMALL_PRIMES[i]) {
+            Some(v) => product = v,
+            None => {
+                count += 1;
+                product = SMALL_PRIMES[i];
+            }
+        }
+        i += 1;
     }
-    for small_prime in Lazy::force(&SMALL_PRIMES) {
-        let mut base: u64 = 1;
-        let mut sum: u64 = 0;
-        for val in &n.value {
-            sum += (base * (val % small_prime)) % small_prime;
-            sum %= small_prime;
-            base *= (BigInt::VALUE_MASK + 1) % small_prime;
-            base %= small_prime;
+    count
+}
+
+const SMALL_PRIME_GROUP_COUNT: usize = small_prime_group_count();
+
+/// 将 `SMALL_PRIMES` 按顺序分组，每组的乘积不超过 u64：(乘积, 起始下标, 结束下标)
+const fn small_prime_groups() -> [(u64, usize, usize); SMALL_PRIME_GROUP_COUNT] {
+    let mut groups = [(0, 0, 0); SMALL_PRIME_GROUP_COUNT];
+    let mut g = 0;
+    let mut start = 0;
+    let mut product: u64 = 1;
+    let mut i = 0;
+    while i < SMALL_PRIME_COUNT {
+        match product.checked_mul(SMALL_PRIMES[i]) {
+            Some(v) => product = v,
+            None => {
+                groups[g] = (product, start, i);
+                g += 1;
+                start = i;
+                product = SMALL_PRIMES[i];
+            }
         }
-        if sum == 0 {
-            return false;
+        i += 1;
+    }
+    groups[g] = (product, start, SMALL_PRIME_COUNT);
+    groups
+}
+
+static SMALL_PRIME_GROUPS: [(u64, usize, usize); SMALL_PRIME_GROUP_COUNT] = small_prime_groups();
+
+/// n 在 10000 以下的最小素因子，没有时为 None。
+/// 每组素数只需对乘积做一次大数除法，再对余数逐个做单字除法
+pub fn small_factor(n: &BigInt) -> Option<u64> {
+    for &(product, start, end) in SMALL_PRIME_GROUPS.iter() {
+        let r = n.rem_u64(product);
+        if let Some(p) = SMALL_PRIMES[start..end]
+            .iter()
+            .find(|&&p| r.is_multiple_of(p))
+        {
+            return Some(*p);
         }
     }
+    None
+}
 
-    // n - 1 = 2^s * d
+/// 对 n < 3.3 * 10^24（包括全部 n < 2^64），以前 12 个素数为见证数即可确定地判断素性
+/// （Sorenson 与 Webster, 2015）
+const DETERMINISTIC_WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// 默认的 Miller-Rabin 轮数。对任意 n（包括刻意构造的合数），误判概率不超过 4^-64
+pub const MR_ROUNDS: usize = 64;
+
+/// 为生成 RSA 素数而随机选取的 bits 位候选数，使误判概率不超过 2^-100 所需的轮数，
+/// 取自 FIPS 186-4 表 C.3；512 位以下表中没有给出，使用 `MR_ROUNDS`
+pub fn mr_rounds_for_keygen(bits: usize) -> usize {
+    match bits {
+        0..512 => MR_ROUNDS,
+        512..1024 => 7,
+        1024..1536 => 4,
+        _ => 3,
+    }
+}
+
+/// Pocklington 检验中尝试的最大见证数
+const POCKLINGTON_MAX_WITNESS: u64 = 1000;
+
+/// Pocklington-Lehmer 素性证明：n - 1 = F * R，factors 为 F 的全部（互不相同的）素因子，
+/// F 取 n - 1 中这些素因子的全部幂次，需要 F > sqrt(n)。若对每个 q 都有 a 使 a^(n-1) = 1 mod n
+/// 且 gcd(a^((n-1)/q) - 1, n) = 1，则 n 为素数。
+/// 返回各 q 对应的见证数 a，可作为素性证书；n 为合数或找不到见证数时为 None
+pub fn pocklington(n: &BigInt, factors: &[BigInt]) -> Result<Option<Vec<u64>>, &'static str> {
+    if *n < TWO {
+        return Ok(None);
+    }
+    if *n == TWO {
+        return Ok(Some(vec![]));
+    }
     let n_sub_1 = n - &ONE;
-    let mut least_nozero_idx = 0;
-    let mut s: u64 = 0;
-    for (i, v) in n_sub_1.value[0..n_sub_1.length].iter().enumerate() {
-        if *v != 0 {
-            least_nozero_idx = i;
-            break;
-        } else {
-            s += BigInt::VALUE_LEN;
+    let mut f = ONE.clone();
+    for q in factors {
+        if q.is_zero() || !(&n_sub_1 % q).is_zero() {
+            return Err("Factor does not divide n - 1");
+        }
+        let mut r = n_sub_1.clone();
+        while *q > ONE && (&r % q).is_zero() {
+            r = &r / q;
+            f = &f * q;
         }
     }
-    for i in 0..BigInt::VALUE_LEN {
-        if (1 << i) & n_sub_1.value[least_nozero_idx] != 0 {
-            s += i;
-            break;
+    if &f * &f <= *n {
+        return Err("Factored part of n - 1 must exceed sqrt(n)");
+    }
+
+    let modulus = Modulus::new(n)?;
+    let mut witnesses = vec![];
+    for q in factors {
+        let exp = &n_sub_1 / q;
+        let mut found = None;
+        for a in 2..POCKLINGTON_MAX_WITNESS.min(u64::try_from(n).unwrap_or(u64::MAX)) {
+            let a_mod = ModInt::new(&BigInt::from(a), &modulus);
+            if a_mod.pow(&n_sub_1).value() != &ONE {
+                // 不满足费马小定理，n 为合数
+                return Ok(None);
+            }
+            let y = a_mod.pow(&exp).into_value();
+            let g = match y.checked_sub(&ONE) {
+                Some(y_sub_1) => y_sub_1.gcd(n),
+                None => n.clone(),
+            };
+            if g == ONE {
+                found = Some(a);
+                break;
+            }
+            if g != *n {
+                // 找到了 n 的非平凡因子
+                return Ok(None);
+            }
+        }
+        match found {
+            Some(a) => witnesses.push(a),
+            None => return Ok(None),
         }
     }
+    Ok(Some(witnesses))
+}
+
+/// `next_prime` 每次筛选的奇数个数
+const NEXT_PRIME_SIEVE_LEN: usize = 1 << 12;
+
+/// 以 10000 以下的奇素数筛 start, start + 2, ..., start + 2(len - 1)，start 须为奇数。
+/// 第 k 项为 true 表示 start + 2k 有小素因子（小素数本身也会被标记）
+pub fn sieve_odd(start: &BigInt, len: usize) -> Vec<bool> {
+    sieve_progression(start, 2, len)
+}
+
+/// 同 `sieve_odd`，筛等差数列 start + k * step，start 与 step 须一奇一偶
+pub fn sieve_progression(start: &BigInt, step: u64, len: usize) -> Vec<bool> {
+    let mut composite = vec![false; len];
+    for &r in &SMALL_PRIMES[1..] {
+        let s = start.rem_u64(r);
+        let st = step % r;
+        if st == 0 {
+            // r | step 时各项模 r 都与 start 相同
+            if s == 0 {
+                composite.iter_mut().for_each(|c| *c = true);
+            }
+            continue;
+        }
+        // k * step = -start (mod r)，step 的逆元由费马小定理得到
+        let (mut inv, mut base, mut exp) = (1, st, r - 2);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                inv = inv * base % r;
+            }
+            base = base * base % r;
+            exp >>= 1;
+        }
+        let first = (r - s) % r * inv % r;
+        for k in (first as usize..len).step_by(r as usize) {
+            composite[k] = true;
+        }
+    }
+    composite
+}
+
+/// 不小于 n 的最小素数。从 n 起每次取一段奇数，以 10000 以下的素数筛去有小因子的，
+/// 再按顺序对筛剩的做 Miller-Rabin 检测
+pub fn next_prime(n: &BigInt) -> BigInt {
+    if *n <= TWO {
+        return TWO.clone();
+    }
+    let mut start = n.clone();
+    if !start.bit(0) {
+        start += &ONE;
+    }
+    loop {
+        let composite = sieve_odd(&start, NEXT_PRIME_SIEVE_LEN);
+        for (k, &sieved) in composite.iter().enumerate() {
+            let candidate = &start + &BigInt::from(2 * k as u64);
+            // 小素数本身也会被筛去，交给 Miller-Rabin 判断
+            if (!sieved || candidate.bit_length() <= 14) && miller_rabin(&candidate) {
+                return candidate;
+            }
+        }
+        start += &BigInt::from(2 * NEXT_PRIME_SIEVE_LEN as u64);
+    }
+}
+
+/// n < 2^64 时使用固定的见证数，结果是确定的；更大的 n 使用 `MR_ROUNDS` 轮随机见证数
+pub fn miller_rabin(n: &BigInt) -> bool {
+    miller_rabin_traced(n, MR_ROUNDS, None)
+}
+
+/// 同 `miller_rabin`，n >= 2^64 时做 rounds 轮
+pub fn miller_rabin_rounds(n: &BigInt, rounds: usize) -> bool {
+    miller_rabin_traced(n, rounds, None)
+}
+
+/// 同 `miller_rabin_rounds`，并记录快速筛查的结果及每一轮的见证数 a 与结论
+pub fn miller_rabin_traced(n: &BigInt, rounds: usize, mut trace: Option<&mut dyn Tracer>) -> bool {
+    // shortcuts
+    if *n < TWO {
+        return false;
+    }
+    if *n == TWO || *n == THREE {
+        return true;
+    }
+    if let Some(small_prime) = small_factor(n) {
+        // 试除表中的素数本身是素数，固定见证数也要求 n 大于它们
+        if *n == BigInt::from(small_prime) {
+            return true;
+        }
+        trace::record(&mut trace, || {
+            (
+                "trial division".to_owned(),
+                format!("divisible by {}", small_prime),
+            )
+        });
+        return false;
+    }
+    trace::record(&mut trace, || {
+        (
+            "trial division".to_owned(),
+            "no factor below 10000".to_owned(),
+        )
+    });
+
+    // n - 1 = 2^s * d
+    let Some(n_sub_1) = n.checked_sub(&ONE) else {
+        return false;
+    };
+    let s = (0..).find(|&i| n_sub_1.bit(i)).unwrap();
     let d = &n_sub_1 >> s;
+    trace::record(&mut trace, || {
+        (
+            "n - 1 = 2^s * d".to_owned(),
+            format!("s = {}, d = {}", s, d.fmt_hex()),
+        )
+    });
 
-    let barrett_m = n.barrett_m();
-    for _ in 0..MR_TEST_TIMES {
-        let mut a;
-        loop {
-            a = barrett_mod(&BigInt::rand(n.length), &barrett_m, n);
-            if a != *ONE {
-                break;
+    let barrett = BarrettContext::new(n);
+    // 见证数在 [2, n - 2] 中均匀选取；n 经过了试除，一定大于固定的见证数
+    let witnesses: Box<dyn Iterator<Item = BigInt>> = if n.bit_length() <= 64 {
+        Box::new(DETERMINISTIC_WITNESSES.iter().map(|&a| BigInt::from(a)))
+    } else {
+        Box::new((0..rounds).map(|_| BigInt::rand_range(&TWO, &n_sub_1)))
+    };
+    for (round, a) in witnesses.enumerate() {
+        if !strong_probable_prime(&barrett, &n_sub_1, &d, s, &a) {
+            trace::record(&mut trace, || {
+                (
+                    format!("round {}: a = {}", round, a.fmt_hex()),
+                    "composite".to_owned(),
+                )
+            });
+            return false;
+        }
+        trace::record(&mut trace, || {
+            (
+                format!("round {}: a = {}", round, a.fmt_hex()),
+                "pass".to_owned(),
+            )
+        });
+    }
+    true
+}
+
+/// Pollard rho，f(x) = x^2 + c，使用 Brent 的环检测并成批计算 gcd。
+/// 迭代 max_iter 次仍未找到时为 None；与 `factor::pollard_rho` 相同，但不受 u128 的限制
+pub fn pollard_rho(n: &BigInt, max_iter: u64) -> Option<BigInt> {
+    if *n <= ONE {
+        return None;
+    }
+    if !n.bit(0) {
+        return Some(TWO.clone());
+    }
+    let barrett = BarrettContext::new(n);
+    let diff = |a: &BigInt, b: &BigInt| if a >= b { a - b } else { b - a };
+    for c in 1..16u64 {
+        let c = BigInt::from(c);
+        let f = |x: &BigInt| {
+            let mut y = &barrett.reduce(&(x * x)) + &c;
+            if y >= *n {
+                y -= n;
+            }
+            y
+        };
+        let (mut x, mut y, mut ys) = (ZERO.clone(), TWO.clone(), TWO.clone());
+        let (mut r, mut q, mut g) = (1u64, ONE.clone(), ONE.clone());
+        let mut iter = 0;
+        while g == ONE && iter < max_iter {
+            x = y.clone();
+            for _ in 0..r {
+                y = f(&y);
             }
+            let mut k = 0;
+            while k < r && g == ONE {
+                ys = y.clone();
+                for _ in 0..std::cmp::min(128, r - k) {
+                    y = f(&y);
+                    q = barrett.reduce(&(&q * &diff(&x, &y)));
+                }
+                g = q.gcd(n);
+                k += 128;
+            }
+            iter += r;
+            r *= 2;
         }
-        // a^d
-        let mut cond = mod_power(&a, &d, &barrett_m, n);
-        if cond != *ONE && cond != n_sub_1 {
-            let mut ok = false;
-            for _ in 1..s {
-                cond = barrett_mod(&(&cond * &cond), &barrett_m, n);
-                if cond == n_sub_1 {
-                    ok = true;
+        if g == *n {
+            // 批量 gcd 越过了因子，逐步回退
+            loop {
+                ys = f(&ys);
+                g = diff(&x, &ys).gcd(n);
+                if g != ONE {
                     break;
                 }
             }
-            if !ok {
-                return false;
+        }
+        if g == ONE {
+            return None;
+        }
+        if g < *n {
+            return Some(g);
+        }
+        // g == n 时换一个多项式重试
+    }
+    None
+}
+
+/// 费马分解，从 a = ceil(sqrt(n)) 起寻找 a^2 - n = b^2，此时 n = (a - b)(a + b)。
+/// n = p * q 时需要约 (p - q)^2 / (8 sqrt(n)) 次迭代，p, q 足够接近时很快
+pub fn fermat(n: &BigInt, max_iter: u64) -> Option<BigInt> {
+    if *n <= ONE {
+        return None;
+    }
+    if !n.bit(0) {
+        return Some(TWO.clone());
+    }
+    let mut a = n.isqrt();
+    if &a * &a < *n {
+        a += &ONE;
+    }
+    for _ in 0..max_iter {
+        let b2 = &(&a * &a) - n;
+        let b = b2.isqrt();
+        if &b * &b == b2 {
+            let f = &a - &b;
+            return if f > ONE { Some(f) } else { None };
+        }
+        a += &ONE;
+    }
+    None
+}
+
+/// `bsgs` 支持的最大模数位数，小步表最多 2^20 项
+pub const BSGS_MAX_BITS: usize = 40;
+
+/// 小步大步法求离散对数：找到最小的 x < p 使 g^x = h mod p，不存在时为 None。
+/// 取 m = ceil(sqrt(p))，小步表记录 g^j (0 <= j < m)，大步依次计算 h * g^(-im)，
+/// 时间与空间都是 O(sqrt(p))，只适用于小模数
+pub fn bsgs(g: &BigInt, h: &BigInt, p: &BigInt) -> Result<Option<BigInt>, &'static str> {
+    if p.bit_length() > BSGS_MAX_BITS {
+        return Err("Modulus is too large for baby-step giant-step");
+    }
+    let modulus = Modulus::new(p)?;
+    let g_inv = g.mod_inverse(p).ok_or("g must be invertible modulo p")?;
+    let m = {
+        let r = p.isqrt();
+        if &r * &r == *p {
+            r
+        } else {
+            &r + &ONE
+        }
+    };
+    let m = u64::try_from(&m).unwrap();
+
+    // 小步：g^j -> j，只保留最小的 j
+    let g = ModInt::new(g, &modulus);
+    let mut table = HashMap::new();
+    let mut x = ModInt::new(&ONE, &modulus);
+    for j in 0..m {
+        table.entry(u64::try_from(x.value()).unwrap()).or_insert(j);
+        x = &x * &g;
+    }
+    // 大步：h * g^(-im) 在表中时 x = im + j
+    let factor = ModInt::new(&g_inv, &modulus).pow(&BigInt::from(m));
+    let mut y = ModInt::new(h, &modulus);
+    for i in 0..m {
+        if let Some(j) = table.get(&u64::try_from(y.value()).unwrap()) {
+            return Ok(Some(BigInt::from(i * m + j)));
+        }
+        y = &y * &factor;
+    }
+    Ok(None)
+}
+
+/// 中国剩余定理：residues 中每一项为 (r_i, n_i)，求 x 使 x = r_i mod n_i，结果小于各模数之积。
+/// 模数不两两互素时 panic
+pub fn crt(residues: &[(BigInt, BigInt)]) -> BigInt {
+    checked_crt(residues).expect("CRT moduli are not pairwise coprime")
+}
+
+/// 同 `crt`，模数不两两互素时为 None
+pub fn checked_crt(residues: &[(BigInt, BigInt)]) -> Option<BigInt> {
+    let mut x = ZERO.clone();
+    let mut m = ONE.clone();
+    // 依次合并：x' = x + m * ((r - x) * m^-1 mod n)
+    for (r, n) in residues {
+        let inv = (&m % n).mod_inverse(n)?;
+        let x_mod_n = &x % n;
+        let r = r % n;
+        let diff = if r >= x_mod_n {
+            &r - &x_mod_n
+        } else {
+            &(&r + n) - &x_mod_n
+        };
+        let t = &(&diff * &inv) % n;
+        x = &x + &(&m * &t);
+        m = &m * n;
+    }
+    Some(x)
+}
+
+/// 以 a 为底的强可能素数检验，其中 n - 1 = 2^s * d：a^d = 1，或 r < s 时某个 a^(2^r * d) = n - 1
+fn strong_probable_prime(
+    barrett: &BarrettContext,
+    n_sub_1: &BigInt,
+    d: &BigInt,
+    s: usize,
+    a: &BigInt,
+) -> bool {
+    let mut x = barrett.mod_pow(a, d);
+    if x == ONE || x == *n_sub_1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = barrett.reduce(&(&x * &x));
+        if x == *n_sub_1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// 雅可比符号 (a / n)，n 为正奇数，否则 panic。a 与 n 不互素时为 0。
+/// 反复提出 a 中的因子 2（(2 / n) 在 n = 3, 5 mod 8 时为 -1），再由二次互反律交换 a 与 n
+pub fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+    assert!(n.bit(0), "Jacobi symbol requires an odd positive n");
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut res = 1;
+    while !a.is_zero() {
+        let tz = a.trailing_zeros();
+        a >>= tz;
+        let n_mod_8 = n.limbs()[0] & 7;
+        if tz % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            res = -res;
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a.limbs()[0] & 3 == 3 && n.limbs()[0] & 3 == 3 {
+            res = -res;
+        }
+        a = &a % &n;
+    }
+    if n == ONE {
+        res
+    } else {
+        0
+    }
+}
+
+/// x mod n，x 可以为负
+fn mod_int_from_i64(x: i64, modulus: &Modulus) -> ModInt<'_> {
+    let v = BigInt::from(x.unsigned_abs());
+    let v = ModInt::new(&v, modulus);
+    if x < 0 {
+        &ModInt::new(&ZERO, modulus) - &v
+    } else {
+        v
+    }
+}
+
+/// x / 2 mod n，n 为奇数
+fn half<'m>(x: &ModInt<'m>, modulus: &'m Modulus) -> ModInt<'m> {
+    let v = x.value();
+    let v = if v.bit(0) { v + modulus.n() } else { v.clone() };
+    ModInt::new(&(&v >> 1usize), modulus)
+}
+
+/// 参数为 P = 1, Q = (1 - D) / 4 的强卢卡斯可能素数检验，n 为不是完全平方数的奇数且 (D / n) = -1。
+/// 记 n + 1 = 2^s * d，U_d = 0，或 r < s 时某个 V_(2^r * d) = 0 则通过
+fn strong_lucas_probable_prime(n: &BigInt, d_param: i64) -> bool {
+    let Ok(modulus) = Modulus::new(n) else {
+        return false;
+    };
+    let m = &modulus;
+    let q = mod_int_from_i64((1 - d_param) / 4, m);
+    let disc = mod_int_from_i64(d_param, m);
+
+    let n_add_1 = n + &ONE;
+    let s = (0..).find(|&i| n_add_1.bit(i)).unwrap();
+    let d = &n_add_1 >> s;
+
+    // 从 k = 1 开始，按 d 的二进制位从高到低计算 U_k, V_k 与 Q^k
+    let mut u = ModInt::new(&ONE, m);
+    let mut v = ModInt::new(&ONE, m);
+    let mut qk = q.clone();
+    for i in (0..d.bit_length() - 1).rev() {
+        // U_2k = U_k * V_k, V_2k = V_k^2 - 2Q^k
+        u = &u * &v;
+        v = &(&v * &v) - &(&qk + &qk);
+        qk = &qk * &qk;
+        if d.bit(i) {
+            // U_(k+1) = (U_k + V_k) / 2, V_(k+1) = (D * U_k + V_k) / 2
+            let u_next = half(&(&u + &v), m);
+            v = half(&(&(&disc * &u) + &v), m);
+            u = u_next;
+            qk = &qk * &q;
+        }
+    }
+
+    let zero = ModInt::new(&ZERO, m);
+    if u == zero {
+        return true;
+    }
+    for _ in 0..s {
+        if v == zero {
+            return true;
+        }
+        v = &(&v * &v) - &(&qk + &qk);
+        qk = &qk * &qk;
+    }
+    false
+}
+
+/// Baillie-PSW 素性检验：试除、以 2 为底的强可能素数检验，再做 Selfridge 方法 A 选取参数的强卢卡斯检验。
+/// 结果是确定的，目前没有已知的伪素数
+pub fn baillie_psw(n: &BigInt) -> bool {
+    if *n < TWO {
+        return false;
+    }
+    if let Some(small_prime) = small_factor(n) {
+        return *n == BigInt::from(small_prime);
+    }
+
+    let n_sub_1 = n - &ONE;
+    let s = (0..).find(|&i| n_sub_1.bit(i)).unwrap();
+    let d = &n_sub_1 >> s;
+    if !strong_probable_prime(&BarrettContext::new(n), &n_sub_1, &d, s, &TWO) {
+        return false;
+    }
+
+    // 完全平方数找不到 (D / n) = -1 的 D
+    let root = n.isqrt();
+    if &root * &root == *n {
+        return false;
+    }
+    // D 依次取 5, -7, 9, -11, ...
+    let mut d_param: i64 = 5;
+    loop {
+        let d_mod_n = if d_param > 0 {
+            BigInt::from(d_param as u64)
+        } else {
+            n - &BigInt::from(d_param.unsigned_abs())
+        };
+        match jacobi(&d_mod_n, n) {
+            -1 => break,
+            // n 经过了试除，大于 |D|，此时 n 有因子
+            0 => return false,
+            _ => {
+                d_param = if d_param > 0 {
+                    -d_param - 2
+                } else {
+                    -d_param + 2
+                }
             }
         }
     }
-    true
+    strong_lucas_probable_prime(n, d_param)
 }
@@ -1,10 +1,25 @@
+use std::collections::BTreeSet;
+use std::fmt;
 use std::sync::Arc;
 
+use crate::armor;
+use crate::audit;
+use crate::bench::BenchResult;
 use crate::bigint::BigInt;
-use crate::{rsa, utils};
+use crate::contacts::Contacts;
+use crate::i18n::{self, Lang};
+use crate::rsa::{DetachedSignature, PrivateKey, PublicKey};
+use crate::settings::Settings;
+use crate::trace::{self, Trace, Tracer};
+use crate::tsa::{self, TimestampToken, Tsa};
+use crate::{entropy, envelope, rsa, utils};
+use iced::futures::channel::oneshot;
+use iced::futures::SinkExt;
 use iced::widget::text_editor::{Action, TextEditor};
-use iced::widget::{text_editor, Button, Column, Row, Text, TextInput};
-use iced::{Element, Sandbox};
+use iced::widget::{
+    image, text_editor, Button, Checkbox, Column, Image, PickList, Row, Scrollable, Text, TextInput,
+};
+use iced::{executor, subscription, Application, Command, Element, Subscription, Theme};
 
 fn content_clear(content: &mut text_editor::Content) {
     content.edit(Action::Move(text_editor::Motion::DocumentStart));
@@ -17,18 +32,323 @@ fn content_replace_text(content: &mut text_editor::Content, string: String) {
     content.edit(Action::Edit(text_editor::Edit::Paste(Arc::new(string))));
 }
 
+/// 密钥长度的预设选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLenPreset {
+    Demo512,
+    Bits1024,
+    Bits2048,
+    Bits3072,
+    Bits4096,
+    Custom,
+}
+
+impl KeyLenPreset {
+    const ALL: [KeyLenPreset; 6] = [
+        KeyLenPreset::Demo512,
+        KeyLenPreset::Bits1024,
+        KeyLenPreset::Bits2048,
+        KeyLenPreset::Bits3072,
+        KeyLenPreset::Bits4096,
+        KeyLenPreset::Custom,
+    ];
+
+    fn len(&self) -> Option<usize> {
+        match self {
+            KeyLenPreset::Demo512 => Some(512),
+            KeyLenPreset::Bits1024 => Some(1024),
+            KeyLenPreset::Bits2048 => Some(2048),
+            KeyLenPreset::Bits3072 => Some(3072),
+            KeyLenPreset::Bits4096 => Some(4096),
+            KeyLenPreset::Custom => None,
+        }
+    }
+
+    fn from_len(len: usize) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.len() == Some(len))
+            .unwrap_or(KeyLenPreset::Custom)
+    }
+}
+
+impl fmt::Display for KeyLenPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyLenPreset::Demo512 => write!(f, "512 (demo)"),
+            KeyLenPreset::Custom => write!(f, "Custom"),
+            p => write!(f, "{}", p.len().unwrap()),
+        }
+    }
+}
+
+/// 公钥、私钥栏及未封装的密文使用的进制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRadix {
+    Hex,
+    Bin,
+    Oct,
+    Dec,
+    Base36,
+}
+
+impl KeyRadix {
+    const ALL: [KeyRadix; 5] = [
+        KeyRadix::Hex,
+        KeyRadix::Bin,
+        KeyRadix::Oct,
+        KeyRadix::Dec,
+        KeyRadix::Base36,
+    ];
+
+    fn radix(&self) -> u32 {
+        match self {
+            KeyRadix::Hex => 16,
+            KeyRadix::Bin => 2,
+            KeyRadix::Oct => 8,
+            KeyRadix::Dec => 10,
+            KeyRadix::Base36 => 36,
+        }
+    }
+}
+
+impl fmt::Display for KeyRadix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyRadix::Hex => write!(f, "hex"),
+            KeyRadix::Bin => write!(f, "bin"),
+            KeyRadix::Oct => write!(f, "oct"),
+            KeyRadix::Dec => write!(f, "dec"),
+            KeyRadix::Base36 => write!(f, "base36"),
+        }
+    }
+}
+
+/// 把以逗号分隔、radix 进制的密文块转为 `decrypt` 使用的十六进制
+fn cipher_from_radix(s: &str, radix: u32) -> Result<String, &'static str> {
+    Ok(s.split(",")
+        .map(|b| BigInt::from_str_radix(b.trim(), radix).map(|x| x.fmt_hex()))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(","))
+}
+
+/// 签名结果为原消息之后接签名文本块（及时间戳），以最后一个签名块的起始行为界，消息本身可以有多行；
+/// 签名不是文本块时第一行为原消息
+fn split_signed(s: &str) -> Option<(&str, &str)> {
+    let at = ["RSA SIGNATURE", "RSA DETACHED SIGNATURE"]
+        .iter()
+        .filter_map(|kind| s.rfind(&format!("\n-----BEGIN {}-----", kind)))
+        .max();
+    match at {
+        Some(i) => Some((&s[..i], &s[i + 1..])),
+        None => s.split_once("\n"),
+    }
+}
+
+/// 后台生成密钥的用时、结果及中间步骤
+type KeygenResult = (u128, Result<(PublicKey, PrivateKey), &'static str>, Trace);
+
+/// 密钥没有实现 Debug，包装后才能放进 Message
+#[derive(Clone)]
+pub struct KeygenDone(Arc<KeygenResult>);
+
+impl fmt::Debug for KeygenDone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeygenDone({} us)", self.0 .0)
+    }
+}
+
+/// 一次后台生成密钥的设置，id 区分先后两次生成
+#[derive(Clone, Copy)]
+struct KeygenJob {
+    id: u64,
+    key_len: usize,
+    params: rsa::KeygenParams,
+    show_work: bool,
+}
+
+/// 在后台线程中生成密钥：生成过程中发送当前素数的进度，结束时发送结果。
+/// 进度最多每 100ms 发送一次，界面来不及处理时直接丢弃
+fn keygen_subscription(job: KeygenJob) -> Subscription<Message> {
+    subscription::channel(job.id, 16, move |mut output| async move {
+        let (done_tx, done_rx) = oneshot::channel();
+        let mut progress_tx = output.clone();
+        std::thread::spawn(move || {
+            let mut trace = Trace::new();
+            let mut last = std::time::Instant::now();
+            let (t, res) = utils::count_time(|| {
+                rsa::gen_keys_with_progress(
+                    job.key_len,
+                    &job.params,
+                    job.show_work.then_some(&mut trace as &mut dyn Tracer),
+                    Some(&mut |idx, tried| {
+                        if last.elapsed().as_millis() >= 100 {
+                            last = std::time::Instant::now();
+                            let _ = progress_tx.try_send(Message::KeygenProgress(idx, tried));
+                        }
+                    }),
+                )
+            });
+            let _ = done_tx.send(KeygenDone(Arc::new((t, res, trace))));
+        });
+        if let Ok(done) = done_rx.await {
+            let _ = output.send(Message::KeygenFinished(done)).await;
+        }
+        // 结果送出后 App 不再订阅，此订阅随即被丢弃
+        loop {
+            iced::futures::future::pending::<()>().await;
+        }
+    })
+}
+
+/// 基准测试中可以计时的操作，名称与命令行的子命令相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchOp {
+    Keygen,
+    Encrypt,
+    Decrypt,
+    Sign,
+}
+
+impl BenchOp {
+    const ALL: [BenchOp; 4] = [
+        BenchOp::Keygen,
+        BenchOp::Encrypt,
+        BenchOp::Decrypt,
+        BenchOp::Sign,
+    ];
+}
+
+impl fmt::Display for BenchOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchOp::Keygen => write!(f, "genkey"),
+            BenchOp::Encrypt => write!(f, "encrypt"),
+            BenchOp::Decrypt => write!(f, "decrypt"),
+            BenchOp::Sign => write!(f, "sign"),
+        }
+    }
+}
+
+/// BenchResult 没有实现 Debug，包装后才能放进 Message
+#[derive(Clone)]
+pub struct BenchDone(Arc<Result<BenchResult, &'static str>>);
+
+impl fmt::Debug for BenchDone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BenchDone")
+    }
+}
+
+/// 一次后台基准测试的设置。生成密钥之外的操作使用当前密钥，消息为输入框中的文本
+#[derive(Clone)]
+struct BenchJob {
+    id: u64,
+    op: BenchOp,
+    runs: usize,
+    key_len: usize,
+    params: rsa::KeygenParams,
+    keys: Option<(PublicKey, PrivateKey)>,
+    message: String,
+}
+
+/// 将操作执行 runs 次并逐次计时，每完成一次调用 progress
+fn run_bench(job: &BenchJob, mut progress: impl FnMut(usize)) -> Result<BenchResult, &'static str> {
+    let mut bench = BenchResult::new(format!("{} {}", job.op, job.key_len));
+    let keys = || job.keys.as_ref().ok_or("You need to regenerate/reset keys");
+    let cipher = match job.op {
+        BenchOp::Decrypt => rsa::encrypt(&job.message, &keys()?.0),
+        _ => String::new(),
+    };
+    for i in 0..job.runs {
+        let (t, res) = utils::count_time(|| match job.op {
+            BenchOp::Keygen => rsa::gen_keys_with_params(job.key_len, &job.params).map(|_| ()),
+            BenchOp::Encrypt => keys().map(|(pub_key, _)| {
+                rsa::encrypt(&job.message, pub_key);
+            }),
+            BenchOp::Decrypt => keys().and_then(|(_, key)| rsa::decrypt(&cipher, key).map(|_| ())),
+            BenchOp::Sign => keys().and_then(|(_, key)| rsa::sign(&job.message, key).map(|_| ())),
+        });
+        res?;
+        bench.times.push(t);
+        progress(i + 1);
+    }
+    Ok(bench)
+}
+
+/// 在后台线程中运行基准测试，每完成一次发送进度，结束时发送结果
+fn bench_subscription(job: BenchJob) -> Subscription<Message> {
+    subscription::channel(("bench", job.id), 16, move |mut output| async move {
+        let (done_tx, done_rx) = oneshot::channel();
+        let mut progress_tx = output.clone();
+        std::thread::spawn(move || {
+            let res = run_bench(&job, |done| {
+                let _ = progress_tx.try_send(Message::BenchProgress(done, job.runs));
+            });
+            let _ = done_tx.send(BenchDone(Arc::new(res)));
+        });
+        if let Ok(done) = done_rx.await {
+            let _ = output.send(Message::BenchFinished(done)).await;
+        }
+        loop {
+            iced::futures::future::pending::<()>().await;
+        }
+    })
+}
+
 pub struct App {
     pub_key: String,
     priv_key: String,
+    key_preset: KeyLenPreset,
     key_length: String,
+    key_length_error: String,
+    /// Miller-Rabin 轮数，留空为自动
+    mr_rounds: String,
+    mr_rounds_error: String,
+    estimate: String,
+    settings: Settings,
+    extra_entropy: String,
+    passphrase: String,
     input: text_editor::Content,
     output: text_editor::Content,
     error: String,
     used_time: String,
-    n: BigInt,
-    d: BigInt,
-    n_barrett_m: BigInt,
+    strength: String,
+    fingerprint: String,
+    show_work: bool,
+    work: String,
+    key_radix: KeyRadix,
+    timestamp: bool,
+    /// 签名与验证签名使用 RSASSA-PSS，签名为十六进制字节串
+    pss: bool,
+    block_encoding: rsa::BlockEncoding,
+    audit: bool,
+    hardware_rng: bool,
+    lang: Lang,
+    tsa: Option<Tsa>,
+    contacts: Contacts,
+    contact_name: String,
+    /// 选作数字信封收件人的联系人名称
+    recipients: BTreeSet<String>,
+    /// 没有可用的密钥时为 None
+    keys: Option<(PublicKey, PrivateKey)>,
     key_len: usize,
+    /// 正在后台生成的密钥，None 为没有在生成
+    keygen: Option<KeygenJob>,
+    /// 下一次生成使用的 id，重置后也不回退，避免与仍在运行的订阅重复
+    next_keygen_id: u64,
+    keygen_progress: String,
+    bench_op: BenchOp,
+    bench_runs: String,
+    /// 导出 CSV、SVG、PNG 的路径，不含扩展名
+    bench_path: String,
+    /// 正在后台运行的基准测试，None 为没有在运行
+    bench: Option<BenchJob>,
+    next_bench_id: u64,
+    bench_result: Option<BenchResult>,
+    /// bench_result 的柱状图，与导出的 PNG 相同
+    bench_chart: Option<image::Handle>,
+    bench_summary: String,
 }
 
 #[derive(Debug, Clone)]
@@ -37,15 +357,46 @@ pub enum Message {
     PrivKeyChanged(String),
     InputChanged(text_editor::Action),
     OutputChanged(text_editor::Action),
+    KeyPresetSelected(KeyLenPreset),
     KeyLenChanged(String),
+    EntropyChanged(String),
+    MrRoundsChanged(String),
+    PassphraseChanged(String),
     SwapPressed,
     GenKeyPressed,
+    KeygenProgress(usize, usize),
+    KeygenFinished(KeygenDone),
+    PassphraseKeyPressed,
     SetKeyPressed,
     EncryptPressed,
     DecryptPressed,
     SignPressed,
     VerifySignPressed,
     ResetPressed,
+    ShowWorkToggled(bool),
+    KeyRadixSelected(KeyRadix),
+    TimestampToggled(bool),
+    TsaKeyPressed,
+    ContactNameChanged(String),
+    AddContactPressed,
+    RemoveContactsPressed,
+    ContactToggled(String, bool),
+    SealPressed,
+    OpenPressed,
+    BenchOpSelected(BenchOp),
+    BenchRunsChanged(String),
+    BenchPathChanged(String),
+    BenchPressed,
+    BenchProgress(usize, usize),
+    BenchFinished(BenchDone),
+    ExportCsvPressed,
+    ExportSvgPressed,
+    ExportPngPressed,
+    PssToggled(bool),
+    BlockEncodingSelected(rsa::BlockEncoding),
+    AuditToggled(bool),
+    HardwareRngToggled(bool),
+    LangSelected(Lang),
 }
 
 impl App {
@@ -54,7 +405,7 @@ impl App {
         txt.strip_suffix("\n").unwrap_or(&txt).to_owned()
     }
     fn set_used_time(&mut self, t: u128) {
-        self.used_time = format!("Used time: {}us", t);
+        self.used_time = i18n::trf("Used time: {}us", &[&t]);
     }
     fn set_input(&mut self, s: String) {
         content_replace_text(&mut self.input, s);
@@ -62,41 +413,150 @@ impl App {
     fn set_output(&mut self, s: String) {
         content_replace_text(&mut self.output, s);
     }
-    fn preform_action<F>(&mut self, func: F)
+    /// 切换密钥长度后重新校验并估计生成时间
+    fn set_key_len(&mut self, len: Result<usize, &'static str>) {
+        match len.and_then(|l| rsa::check_key_len(l).map(|_| l)) {
+            Ok(l) => {
+                self.key_len = l;
+                self.key_length_error.clear();
+                self.estimate = match self.settings.estimate_keygen(l) {
+                    Some(t) => i18n::trf(
+                        "Estimated generation time: ~{}s",
+                        &[&format!("{:.2}", t as f64 / 1e6)],
+                    ),
+                    None => i18n::tr("Estimated generation time: unknown").to_owned(),
+                };
+            }
+            Err(e) => {
+                self.key_length_error = e.to_owned();
+                self.estimate.clear();
+            }
+        }
+    }
+    /// 按当前进制在公钥、私钥栏显示密钥
+    fn set_key_text(&mut self) {
+        let Some((pub_key, priv_key)) = &self.keys else {
+            return;
+        };
+        (self.pub_key, self.priv_key) = match self.key_radix {
+            KeyRadix::Hex => rsa::fmt_key(pub_key, priv_key),
+            r => rsa::fmt_key_radix(pub_key, priv_key, r.radix()),
+        };
+    }
+    fn set_work(&mut self, trace: Trace) {
+        self.work = trace::fmt_trace(&trace);
+    }
+    /// 按当前设置生成密钥时使用的参数
+    fn keygen_params(&self) -> rsa::KeygenParams {
+        rsa::KeygenParams {
+            mr_rounds: self.settings.mr_rounds,
+            ..rsa::KeygenParams::default()
+        }
+    }
+
+    /// 密钥生成的输入只记录密钥长度，不涉及口令
+    fn audit_keygen(&self, res: &Result<(PublicKey, PrivateKey), &'static str>, t: u128) {
+        let status = if res.is_ok() { "ok" } else { "failed" };
+        let n = res.as_ref().ok().map(|(pub_key, _)| pub_key.n());
+        audit::log("genkey", n, self.key_len.to_string().as_bytes(), status, t);
+    }
+    /// 执行 func 并计时；若开启了 Show work，同时收集中间步骤。
+    /// func 返回 Err 表示操作失败，其内容同样显示在输出框中
+    fn preform_action<F>(&mut self, operation: &str, func: F)
     where
-        F: Fn(String) -> String,
+        F: FnOnce(String, Option<&mut dyn Tracer>) -> Result<String, String>,
     {
         let txt = self.get_strip_input();
-        let (t, res) = utils::count_time(|| func(txt.clone()));
+        let mut trace = Trace::new();
+        let show_work = self.show_work;
+        let (t, res) = utils::count_time(|| {
+            func(
+                txt.clone(),
+                show_work.then_some(&mut trace as &mut dyn Tracer),
+            )
+        });
+        let (status, res) = match res {
+            Ok(r) => ("ok", r),
+            Err(r) => ("failed", r),
+        };
+        let n = self.keys.as_ref().map(|(pub_key, _)| pub_key.n());
+        audit::log(operation, n, txt.as_bytes(), status, t);
         self.set_used_time(t);
         self.set_output(res);
+        self.set_work(trace);
     }
-}
 
-impl Sandbox for App {
-    type Message = Message;
-
-    fn new() -> Self {
-        App {
+    fn init() -> Self {
+        let mut app = App {
             pub_key: String::new(),
             priv_key: String::new(),
-            key_length: String::from("768"),
+            key_preset: KeyLenPreset::Bits1024,
+            key_length: String::from("1024"),
+            key_length_error: String::new(),
+            mr_rounds: String::new(),
+            mr_rounds_error: String::new(),
+            estimate: String::new(),
+            settings: Settings::load(),
+            extra_entropy: String::new(),
+            passphrase: String::new(),
             input: text_editor::Content::new(),
             output: text_editor::Content::new(),
             error: String::new(),
             used_time: String::new(),
-            n: BigInt::with_capacity(1),
-            d: BigInt::with_capacity(1),
-            n_barrett_m: BigInt::with_capacity(1),
-            key_len: 768,
+            strength: String::new(),
+            fingerprint: String::new(),
+            show_work: false,
+            work: String::new(),
+            key_radix: KeyRadix::Hex,
+            timestamp: false,
+            pss: false,
+            block_encoding: rsa::BlockEncoding::Hex,
+            audit: audit::is_enabled(),
+            hardware_rng: false,
+            lang: i18n::lang(),
+            tsa: Tsa::load().ok().flatten(),
+            contacts: Contacts::load(),
+            contact_name: String::new(),
+            recipients: BTreeSet::new(),
+            keys: None,
+            key_len: 1024,
+            keygen: None,
+            next_keygen_id: 0,
+            keygen_progress: String::new(),
+            bench_op: BenchOp::Keygen,
+            bench_runs: String::from("10"),
+            bench_path: String::from("bench"),
+            bench: None,
+            next_bench_id: 0,
+            bench_result: None,
+            bench_chart: None,
+            bench_summary: String::new(),
+        };
+        app.set_key_len(Ok(app.key_len));
+        app.hardware_rng = app.settings.hardware_rng;
+        entropy::set_hardware(app.hardware_rng);
+        if let Some(r) = app.settings.mr_rounds {
+            app.mr_rounds = r.to_string();
         }
+        app
+    }
+}
+
+impl Application for App {
+    type Executor = executor::Default;
+    type Message = Message;
+    type Theme = Theme;
+    type Flags = ();
+
+    fn new(_flags: ()) -> (Self, Command<Message>) {
+        (App::init(), Command::none())
     }
 
     fn title(&self) -> String {
         String::from("RSA")
     }
 
-    fn update(&mut self, message: Message) {
+    fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::PubKeyChanged(s) => {
                 self.pub_key = s;
@@ -111,13 +571,57 @@ impl Sandbox for App {
                 Action::Edit(_) => {}
                 _ => self.output.edit(s),
             },
-            Message::KeyLenChanged(s) => match s.parse::<usize>() {
-                Ok(l) => {
-                    self.key_len = l;
-                    self.key_length = s;
+            Message::KeyPresetSelected(p) => {
+                self.key_preset = p;
+                match p.len() {
+                    Some(l) => {
+                        self.key_length = l.to_string();
+                        self.set_key_len(Ok(l));
+                    }
+                    None => self.set_key_len(
+                        self.key_length
+                            .parse()
+                            .map_err(|_| "Key length must be a number"),
+                    ),
                 }
-                Err(_) => {}
-            },
+            }
+            Message::KeyLenChanged(s) => {
+                self.set_key_len(s.parse().map_err(|_| "Key length must be a number"));
+                self.key_length = s;
+            }
+            Message::EntropyChanged(s) => {
+                // 输入内容与按键时间一并混入随机数源
+                let nanos = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_nanos())
+                    .unwrap_or(0);
+                entropy::add_entropy(&nanos.to_le_bytes());
+                entropy::add_entropy(s.as_bytes());
+                self.extra_entropy = s;
+            }
+            Message::MrRoundsChanged(s) => {
+                let rounds = if s.trim().is_empty() {
+                    Ok(None)
+                } else {
+                    s.trim()
+                        .parse()
+                        .map_err(|_| "Miller-Rabin rounds must be a positive number")
+                        .and_then(rsa::check_mr_rounds)
+                        .map(Some)
+                };
+                match rounds {
+                    Ok(r) => {
+                        self.mr_rounds_error.clear();
+                        self.settings.mr_rounds = r;
+                        let _ = self.settings.save();
+                    }
+                    Err(e) => self.mr_rounds_error = e.to_owned(),
+                }
+                self.mr_rounds = s;
+            }
+            Message::PassphraseChanged(s) => {
+                self.passphrase = s;
+            }
             Message::SwapPressed => {
                 let mut output_text = self.output.text();
                 output_text = output_text
@@ -129,87 +633,506 @@ impl Sandbox for App {
             }
             Message::GenKeyPressed => {
                 self.error.clear();
-                let t;
-                (t, (self.n, self.d)) = utils::count_time(|| rsa::gen_keys(self.key_len));
-                self.set_used_time(t);
-                self.n_barrett_m = self.n.barrett_m();
-                (self.pub_key, self.priv_key) = rsa::fmt_key(&self.n, &self.d);
-            }
-            Message::SetKeyPressed => match rsa::key_from_str(&self.pub_key, &self.priv_key) {
-                Ok(r) => {
-                    self.error.clear();
-                    (self.n, self.d, self.key_len) = r;
-                    self.n_barrett_m = self.n.barrett_m();
-                    self.key_length = format!("{}", self.key_len);
+                // 在后台生成，界面保持响应并显示进度；生成期间禁用生成按钮
+                self.keygen = Some(KeygenJob {
+                    id: self.next_keygen_id,
+                    key_len: self.key_len,
+                    params: self.keygen_params(),
+                    show_work: self.show_work,
+                });
+                self.next_keygen_id += 1;
+                self.keygen_progress.clear();
+            }
+            Message::KeygenProgress(idx, tried) => {
+                if self.keygen.is_some() {
+                    self.keygen_progress = i18n::trf(
+                        "Generating prime {}: {} candidates tested",
+                        &[&(idx + 1), &tried],
+                    );
                 }
-                Err(e) => self.error = e.to_owned(),
-            },
+            }
+            Message::KeygenFinished(done) => {
+                let Some(job) = self.keygen.take() else {
+                    return Command::none();
+                };
+                self.keygen_progress.clear();
+                let (t, res, trace) = Arc::try_unwrap(done.0).unwrap_or_else(|a| (*a).clone());
+                self.key_len = job.key_len;
+                self.audit_keygen(&res, t);
+                match res {
+                    Ok((pub_key, priv_key)) => {
+                        self.strength = rsa::fmt_strength(pub_key.n());
+                        self.fingerprint = pub_key.fingerprint().to_string();
+                        self.keys = Some((pub_key, priv_key));
+                        self.set_used_time(t);
+                        self.settings.record_keygen(self.key_len, t);
+                        // 保存失败只影响之后的估计，不打断生成
+                        let _ = self.settings.save();
+                        self.set_key_len(Ok(self.key_len));
+                        self.set_work(trace);
+                        self.set_key_text();
+                    }
+                    Err(e) => self.error = e.to_owned(),
+                }
+            }
+            Message::PassphraseKeyPressed => {
+                self.error.clear();
+                let (t, res) = utils::count_time(|| {
+                    rsa::gen_keys_from_passphrase(self.key_len, &self.passphrase)
+                });
+                self.audit_keygen(&res, t);
+                match res {
+                    Ok((pub_key, priv_key)) => {
+                        self.strength = i18n::trf(
+                            "{} (passphrase-derived demo key, only as strong as the passphrase)",
+                            &[&rsa::fmt_strength(pub_key.n())],
+                        );
+                        self.fingerprint = pub_key.fingerprint().to_string();
+                        self.keys = Some((pub_key, priv_key));
+                        self.set_used_time(t);
+                        self.work.clear();
+                        self.set_key_text();
+                    }
+                    Err(e) => self.error = e.to_owned(),
+                }
+            }
+            Message::SetKeyPressed => {
+                let res = match self.key_radix {
+                    KeyRadix::Hex => rsa::key_from_str(&self.pub_key, &self.priv_key),
+                    r => rsa::key_from_str_radix(&self.pub_key, &self.priv_key, r.radix()),
+                };
+                match res {
+                    Ok((pub_key, priv_key)) => {
+                        // 校验密钥是否自洽，有问题时逐行列出，不设置密钥
+                        let problems = priv_key.validate();
+                        if !problems.is_empty() {
+                            self.error = problems
+                                .iter()
+                                .map(|p| p.message())
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            return Command::none();
+                        }
+                        self.error.clear();
+                        self.key_len = pub_key.key_len();
+                        self.strength = rsa::fmt_strength(pub_key.n());
+                        self.fingerprint = pub_key.fingerprint().to_string();
+                        self.keys = Some((pub_key, priv_key));
+                        self.key_length = format!("{}", self.key_len);
+                        self.key_preset = KeyLenPreset::from_len(self.key_len);
+                        self.set_key_len(Ok(self.key_len));
+                    }
+                    Err(e) => self.error = e.to_owned(),
+                }
+            }
             Message::EncryptPressed => {
-                if self.error != "" {
+                if self.error != "" || self.keys.is_none() {
                     self.error = String::from("You need to regenerate/reset keys");
                 } else {
-                    let n = self.n.clone();
-                    let m = self.n_barrett_m.clone();
-                    self.preform_action(|s| rsa::encrypt(&s, &n, &m));
+                    let pub_key = self.keys.as_ref().unwrap().0.clone();
+                    let encoding = self.block_encoding;
+                    self.preform_action("encrypt", |s, tr| {
+                        Ok(armor::armor(
+                            "RSA MESSAGE",
+                            rsa::encrypt_traced(&s, &pub_key, encoding, tr).as_bytes(),
+                        ))
+                    });
                 }
             }
             Message::DecryptPressed => {
-                if self.error != "" {
+                if self.error != "" || self.keys.is_none() {
                     self.error = String::from("You need to regenerate/reset keys");
                 } else {
-                    let n = self.n.clone();
-                    let m = self.n_barrett_m.clone();
-                    let d = self.d.clone();
-                    self.preform_action(|s| rsa::decrypt(&s, &n, &m, &d));
+                    let key = self.keys.as_ref().unwrap().1.clone();
+                    let radix = self.key_radix;
+                    self.preform_action("decrypt", |s, tr| {
+                        // 先校验文本块，以便区分复制损坏与解密失败
+                        let cipher = if armor::is_armored(&s) {
+                            armor::dearmor_text("RSA MESSAGE", &s)
+                                .map_err(|e| i18n::tr(e.message()).to_owned())?
+                        } else if radix != KeyRadix::Hex {
+                            cipher_from_radix(&s, radix.radix())
+                                .map_err(|e| i18n::tr(e).to_owned())?
+                        } else {
+                            s
+                        };
+                        rsa::decrypt_traced(&cipher, &key, tr).map_err(|e| i18n::tr(e).to_owned())
+                    });
                 }
             }
             Message::SignPressed => {
-                if self.error != "" {
+                if self.error != "" || self.keys.is_none() {
                     self.error = String::from("You need to regenerate/reset keys");
                 } else {
-                    let n = self.n.clone();
-                    let m = self.n_barrett_m.clone();
-                    let d = self.d.clone();
-                    self.preform_action(|s| format!("{}\n{}", s, rsa::sign(&s, &n, &m, &d)));
+                    // 本地 TSA 的密钥对在第一次需要时生成并保存，之后每次启动时读入
+                    if self.timestamp && self.tsa.is_none() {
+                        match Tsa::load_or_new(1024) {
+                            Ok(t) => self.tsa = Some(t),
+                            Err(e) => {
+                                self.error = e.to_owned();
+                                return Command::none();
+                            }
+                        }
+                    }
+                    let key = self.keys.as_ref().unwrap().1.clone();
+                    let tsa = self.tsa.clone().filter(|_| self.timestamp);
+                    let pss = self.pss;
+                    let encoding = self.block_encoding;
+                    self.preform_action("sign", |s, tr| {
+                        let sign = if pss {
+                            let sign = rsa::sign_pss(s.as_bytes(), &key, rsa::PSS_SALT_LEN)
+                                .map_err(|e| i18n::tr(e).to_owned())?;
+                            encoding.encode_bytes(&sign)
+                        } else {
+                            rsa::sign_traced(&s, &key, encoding, tr)
+                                .map_err(|e| i18n::tr(e).to_owned())?
+                        };
+                        let armored = armor::armor("RSA SIGNATURE", sign.as_bytes());
+                        Ok(match tsa {
+                            Some(tsa) => {
+                                let token = tsa.stamp(&sign).map_err(|e| i18n::tr(e).to_owned())?;
+                                format!(
+                                    "{}\n{}\n{}",
+                                    s,
+                                    armored,
+                                    armor::armor("RSA TIMESTAMP", token.fmt().as_bytes())
+                                )
+                            }
+                            None => format!("{}\n{}", s, armored),
+                        })
+                    });
                 }
             }
             Message::VerifySignPressed => {
-                if self.error != "" {
+                if self.error != "" || self.keys.is_none() {
                     self.error = String::from("You need to regenerate/reset keys");
                 } else {
-                    let n = self.n.clone();
-                    let m = self.n_barrett_m.clone();
-                    self.preform_action(|s| {
-                        let sp = s.split("\n").collect::<Vec<_>>();
-                        if sp.len() != 2 {
-                            return String::from("Invalid input for verify sign");
+                    let pub_key = self.keys.as_ref().unwrap().0.clone();
+                    let tsa = self.tsa.clone();
+                    let pss = self.pss;
+                    self.preform_action("verify", |s, tr| {
+                        // 原消息之后为签名及可选的时间戳令牌
+                        let Some((msg, rest)) = split_signed(&s) else {
+                            return Err(i18n::tr("Invalid input for verify sign").to_owned());
+                        };
+                        let (sign, detached, token) = if armor::is_armored(rest) {
+                            let blocks = armor::dearmor_all(rest)
+                                .map_err(|e| i18n::tr(e.message()).to_owned())?;
+                            let find = |kind| {
+                                blocks
+                                    .iter()
+                                    .find(|(k, _)| k == kind)
+                                    .and_then(|(_, data)| String::from_utf8(data.clone()).ok())
+                            };
+                            (
+                                find("RSA SIGNATURE"),
+                                find("RSA DETACHED SIGNATURE"),
+                                find("RSA TIMESTAMP"),
+                            )
+                        } else {
+                            let mut sp = rest.split("\n").map(str::to_owned);
+                            (sp.next(), None, sp.next())
+                        };
+                        // 分离式签名自带方案，不受 PSS 选项影响
+                        let (valid, res) = match (&sign, &detached) {
+                            (_, Some(d)) => {
+                                let valid = DetachedSignature::parse(d).is_ok_and(|d| {
+                                    rsa::verify_detached(msg.as_bytes(), &d, &pub_key)
+                                });
+                                (valid, valid.to_string())
+                            }
+                            (Some(sign), None) if pss => {
+                                let valid = rsa::decode_block(sign).is_ok_and(|sign| {
+                                    rsa::ver_sign_pss(
+                                        msg.as_bytes(),
+                                        &sign,
+                                        &pub_key,
+                                        rsa::PSS_SALT_LEN,
+                                    )
+                                });
+                                (valid, valid.to_string())
+                            }
+                            (Some(sign), None) => {
+                                let report = rsa::ver_sign_traced(msg, sign, &pub_key, tr);
+                                let res = format!("{}\n{}", report.valid(), report.fmt_report());
+                                (report.valid(), res)
+                            }
+                            (None, None) => {
+                                return Err(i18n::tr("Invalid input for verify sign").to_owned())
+                            }
+                        };
+                        let sign = detached.or(sign).unwrap_or_default();
+                        let Some(token) = token else {
+                            return if valid { Ok(res) } else { Err(res) };
+                        };
+                        let stamp = match (TimestampToken::parse(&token), tsa) {
+                            (Err(e), _) => i18n::tr(e).to_owned(),
+                            (Ok(_), None) => {
+                                i18n::tr("No local TSA key to check timestamp").to_owned()
+                            }
+                            (Ok(token), Some(tsa)) => format!(
+                                "timestamp {}: {}",
+                                token.time,
+                                tsa::verify_token(&token, &sign, &tsa.pub_key)
+                            ),
+                        };
+                        let res = format!("{}\n{}", res, stamp);
+                        if valid {
+                            Ok(res)
+                        } else {
+                            Err(res)
                         }
-                        let msg = sp[0];
-                        let sign = sp[1];
-                        let (res, ver_msg) = rsa::ver_sign(msg, sign, &n, &m);
-                        format!("{}\n{}", res, ver_msg)
                     });
                 }
             }
             Message::ResetPressed => {
-                *self = App {
-                    pub_key: String::new(),
-                    priv_key: String::new(),
-                    key_length: String::from("768"),
-                    input: text_editor::Content::new(),
-                    output: text_editor::Content::new(),
-                    error: String::new(),
-                    used_time: String::new(),
-                    n: BigInt::with_capacity(1),
-                    d: BigInt::with_capacity(1),
-                    n_barrett_m: BigInt::with_capacity(1),
-                    key_len: 768,
+                // TSA 密钥与用户密钥无关，重置后保留，以便继续验证之前签发的时间戳
+                let tsa = self.tsa.take();
+                let next_keygen_id = self.next_keygen_id;
+                let next_bench_id = self.next_bench_id;
+                *self = App::init();
+                self.tsa = tsa;
+                self.next_keygen_id = next_keygen_id;
+                self.next_bench_id = next_bench_id;
+            }
+            Message::TimestampToggled(b) => {
+                self.timestamp = b;
+            }
+            Message::TsaKeyPressed => {
+                // 以当前密钥作为 TSA 密钥并保存，用于验证其他地方签发的时间戳
+                let Some((pub_key, key)) = self.keys.clone() else {
+                    self.error = String::from("You need to regenerate/reset keys");
+                    return Command::none();
+                };
+                let tsa = Tsa::from_keys(pub_key, key);
+                if tsa.save().is_err() {
+                    self.error = String::from("Error saving TSA key");
+                }
+                self.tsa = Some(tsa);
+            }
+            Message::BenchOpSelected(op) => {
+                self.bench_op = op;
+            }
+            Message::BenchRunsChanged(s) => {
+                self.bench_runs = s;
+            }
+            Message::BenchPathChanged(s) => {
+                self.bench_path = s;
+            }
+            Message::BenchPressed => {
+                let runs = match self.bench_runs.trim().parse::<usize>() {
+                    Ok(r) if r > 0 => r,
+                    _ => {
+                        self.error = String::from("Number of benchmark runs must be at least 1");
+                        return Command::none();
+                    }
+                };
+                if self.bench_op != BenchOp::Keygen && self.keys.is_none() {
+                    self.error = String::from("You need to regenerate/reset keys");
+                    return Command::none();
+                }
+                self.error.clear();
+                // 与生成密钥一样在后台运行，期间禁用开始按钮
+                self.bench = Some(BenchJob {
+                    id: self.next_bench_id,
+                    op: self.bench_op,
+                    runs,
+                    key_len: self.key_len,
+                    params: self.keygen_params(),
+                    keys: self.keys.clone(),
+                    message: self.get_strip_input(),
+                });
+                self.next_bench_id += 1;
+                self.bench_result = None;
+                self.bench_chart = None;
+                self.bench_summary.clear();
+            }
+            Message::BenchProgress(done, runs) => {
+                if self.bench.is_some() {
+                    self.bench_summary =
+                        i18n::trf("Benchmark: {} of {} runs done", &[&done, &runs]);
+                }
+            }
+            Message::BenchFinished(done) => {
+                let Some(job) = self.bench.take() else {
+                    return Command::none();
+                };
+                match Arc::try_unwrap(done.0).unwrap_or_else(|a| (*a).clone()) {
+                    Ok(bench) => {
+                        // 与命令行的 genkey 一样，计时结果供估计生成时间
+                        if job.op == BenchOp::Keygen {
+                            for t in &bench.times {
+                                self.settings.record_keygen(job.key_len, *t);
+                            }
+                            let _ = self.settings.save();
+                            self.set_key_len(Ok(self.key_len));
+                        }
+                        self.bench_summary = i18n::trf(
+                            "{}: mean {}us over {} runs",
+                            &[&bench.title, &(bench.mean() as u128), &bench.times.len()],
+                        );
+                        self.bench_chart = Some(image::Handle::from_memory(bench.to_png()));
+                        self.bench_result = Some(bench);
+                    }
+                    Err(e) => {
+                        self.bench_summary.clear();
+                        self.error = e.to_owned();
+                    }
+                }
+            }
+            Message::ExportCsvPressed => {
+                if let Some(bench) = &self.bench_result {
+                    if bench.save_csv(&format!("{}.csv", self.bench_path)).is_err() {
+                        self.error = String::from("write csv failed");
+                    }
+                }
+            }
+            Message::ExportSvgPressed => {
+                if let Some(bench) = &self.bench_result {
+                    if bench.save_svg(&format!("{}.svg", self.bench_path)).is_err() {
+                        self.error = String::from("write svg failed");
+                    }
+                }
+            }
+            Message::ExportPngPressed => {
+                if let Some(bench) = &self.bench_result {
+                    if bench.save_png(&format!("{}.png", self.bench_path)).is_err() {
+                        self.error = String::from("write png failed");
+                    }
+                }
+            }
+            Message::ContactNameChanged(s) => {
+                self.contact_name = s;
+            }
+            Message::AddContactPressed => {
+                // 以当前设置的公钥添加联系人
+                let Some((pub_key, _)) = self.keys.clone() else {
+                    self.error = String::from("You need to regenerate/reset keys");
+                    return Command::none();
+                };
+                match self.contacts.add(&self.contact_name, pub_key) {
+                    Ok(()) => {
+                        self.contact_name.clear();
+                        if self.contacts.save().is_err() {
+                            self.error = String::from("Error saving contacts");
+                        }
+                    }
+                    Err(e) => self.error = e.to_owned(),
+                }
+            }
+            Message::RemoveContactsPressed => {
+                for name in std::mem::take(&mut self.recipients) {
+                    self.contacts.remove(&name);
+                }
+                if self.contacts.save().is_err() {
+                    self.error = String::from("Error saving contacts");
+                }
+            }
+            Message::ContactToggled(name, b) => {
+                if b {
+                    self.recipients.insert(name);
+                } else {
+                    self.recipients.remove(&name);
+                }
+            }
+            Message::SealPressed => {
+                let pub_keys: Vec<PublicKey> = self
+                    .recipients
+                    .iter()
+                    .filter_map(|name| self.contacts.find(name))
+                    .map(|c| c.pub_key.clone())
+                    .collect();
+                if pub_keys.is_empty() {
+                    self.error = String::from("Select at least one contact as recipient");
+                } else {
+                    self.preform_action("seal", |s, _| {
+                        let blob = envelope::seal(s.as_bytes(), &pub_keys)
+                            .map_err(|e| i18n::tr(e).to_owned())?;
+                        Ok(armor::armor("RSA ENVELOPE", &blob))
+                    });
+                }
+            }
+            Message::OpenPressed => {
+                if !self.error.is_empty() || self.keys.is_none() {
+                    self.error = String::from("You need to regenerate/reset keys");
+                } else {
+                    let key = self.keys.as_ref().unwrap().1.clone();
+                    self.preform_action("open", |s, _| {
+                        let blob = armor::dearmor("RSA ENVELOPE", &s)
+                            .map_err(|e| i18n::tr(e.message()).to_owned())?;
+                        let plain =
+                            envelope::open(&blob, &key).map_err(|e| i18n::tr(e).to_owned())?;
+                        String::from_utf8(plain)
+                            .map_err(|_| i18n::tr("Input is not valid text").to_owned())
+                    });
+                }
+            }
+            Message::PssToggled(b) => {
+                self.pss = b;
+            }
+            Message::BlockEncodingSelected(e) => {
+                self.block_encoding = e;
+            }
+            Message::AuditToggled(b) => {
+                self.audit = b;
+                audit::set_enabled(b);
+            }
+            Message::LangSelected(l) => {
+                self.lang = l;
+                i18n::set_lang(l);
+                // 已显示的错误在绘制时翻译，预计用时需要重新生成
+                if self.key_length_error.is_empty() {
+                    self.set_key_len(Ok(self.key_len));
+                }
+            }
+            Message::HardwareRngToggled(b) => {
+                self.hardware_rng = b;
+                entropy::set_hardware(b);
+                self.settings.hardware_rng = b;
+                let _ = self.settings.save();
+            }
+            Message::KeyRadixSelected(r) => {
+                self.key_radix = r;
+                // 已设置密钥时按新的进制重新显示
+                self.set_key_text();
+            }
+            Message::ShowWorkToggled(b) => {
+                self.show_work = b;
+                if !b {
+                    self.work.clear();
                 }
             }
         }
+        Command::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let keygen = match self.keygen {
+            Some(job) => keygen_subscription(job),
+            None => Subscription::none(),
+        };
+        let bench = match &self.bench {
+            Some(job) => bench_subscription(job.clone()),
+            None => Subscription::none(),
+        };
+        Subscription::batch([keygen, bench])
     }
 
     fn view(&self) -> Element<Message> {
+        // 密钥长度或轮数无效时禁用，正在生成时也禁用
+        let gen_key = Button::new("Generate Key").padding(10);
+        let passphrase_key = Button::new("Key From Passphrase (demo)").padding(10);
+        let (gen_key, passphrase_key) = if self.keygen.is_none()
+            && self.key_length_error.is_empty()
+            && self.mr_rounds_error.is_empty()
+        {
+            (
+                gen_key.on_press(Message::GenKeyPressed),
+                passphrase_key.on_press(Message::PassphraseKeyPressed),
+            )
+        } else {
+            (gen_key, passphrase_key)
+        };
         Column::new()
             .push(
                 Row::new()
@@ -225,18 +1148,53 @@ impl Sandbox for App {
                             .password(),
                     )
                     .push(
+                        PickList::new(
+                            &KeyLenPreset::ALL[..],
+                            Some(self.key_preset),
+                            Message::KeyPresetSelected,
+                        )
+                        .padding(10),
+                    )
+                    .push(if self.key_preset == KeyLenPreset::Custom {
                         TextInput::new("密钥长度", &self.key_length)
                             .padding(10)
-                            .on_input(Message::KeyLenChanged),
+                            .on_input(Message::KeyLenChanged)
+                    } else {
+                        TextInput::new("密钥长度", &self.key_length).padding(10)
+                    })
+                    .push(
+                        TextInput::new("MR 轮数（留空为自动）", &self.mr_rounds)
+                            .padding(10)
+                            .on_input(Message::MrRoundsChanged),
+                    )
+                    .push(
+                        TextInput::new("额外熵（随意输入）", &self.extra_entropy)
+                            .padding(10)
+                            .on_input(Message::EntropyChanged),
+                    )
+                    .push(
+                        TextInput::new("口令（演示用）", &self.passphrase)
+                            .padding(10)
+                            .on_input(Message::PassphraseChanged)
+                            .password(),
+                    )
+                    .push(
+                        PickList::new(
+                            &KeyRadix::ALL[..],
+                            Some(self.key_radix),
+                            Message::KeyRadixSelected,
+                        )
+                        .padding(10),
+                    )
+                    .push(
+                        PickList::new(&Lang::ALL[..], Some(self.lang), Message::LangSelected)
+                            .padding(10),
                     ),
             )
             .push(
                 Row::new()
-                    .push(
-                        Button::new("Generate Key")
-                            .on_press(Message::GenKeyPressed)
-                            .padding(10),
-                    )
+                    .push(gen_key)
+                    .push(passphrase_key)
                     .push(
                         Button::new("Set Key")
                             .on_press(Message::SetKeyPressed)
@@ -267,9 +1225,143 @@ impl Sandbox for App {
                             .on_press(Message::ResetPressed)
                             .padding(10),
                     )
+                    .push(
+                        PickList::new(
+                            &rsa::BlockEncoding::ALL[..],
+                            Some(self.block_encoding),
+                            Message::BlockEncodingSelected,
+                        )
+                        .padding(10),
+                    )
+                    .push(Checkbox::new(
+                        "Timestamp",
+                        self.timestamp,
+                        Message::TimestampToggled,
+                    ))
+                    .push(
+                        Button::new("Use as TSA Key")
+                            .on_press(Message::TsaKeyPressed)
+                            .padding(10),
+                    )
+                    .push(Checkbox::new("PSS", self.pss, Message::PssToggled))
+                    .push(Checkbox::new(
+                        "Hardware RNG",
+                        self.hardware_rng,
+                        Message::HardwareRngToggled,
+                    ))
+                    .push(Checkbox::new(
+                        "Audit log",
+                        self.audit,
+                        Message::AuditToggled,
+                    ))
+                    .push(Checkbox::new(
+                        "Show work",
+                        self.show_work,
+                        Message::ShowWorkToggled,
+                    ))
                     .push(Text::new(&self.used_time)),
             )
-            .push(Text::new(&self.error))
+            .push(
+                self.contacts.list.iter().fold(
+                    Row::new()
+                        .push(
+                            TextInput::new("联系人名称", &self.contact_name)
+                                .padding(10)
+                                .on_input(Message::ContactNameChanged),
+                        )
+                        .push(
+                            Button::new("Add Contact")
+                                .on_press(Message::AddContactPressed)
+                                .padding(10),
+                        )
+                        .push(
+                            Button::new("Remove Contacts")
+                                .on_press(Message::RemoveContactsPressed)
+                                .padding(10),
+                        )
+                        .push(
+                            Button::new("Seal")
+                                .on_press(Message::SealPressed)
+                                .padding(10),
+                        )
+                        .push(
+                            Button::new("Open")
+                                .on_press(Message::OpenPressed)
+                                .padding(10),
+                        ),
+                    |row, c| {
+                        let name = c.name.clone();
+                        row.push(Checkbox::new(
+                            &c.name,
+                            self.recipients.contains(&c.name),
+                            move |b| Message::ContactToggled(name.clone(), b),
+                        ))
+                    },
+                ),
+            )
+            .push({
+                let bench = Button::new("Run Benchmark").padding(10);
+                let csv = Button::new("Export CSV").padding(10);
+                let svg = Button::new("Export SVG").padding(10);
+                let png = Button::new("Export PNG").padding(10);
+                Row::new()
+                    .push(
+                        PickList::new(
+                            &BenchOp::ALL[..],
+                            Some(self.bench_op),
+                            Message::BenchOpSelected,
+                        )
+                        .padding(10),
+                    )
+                    .push(
+                        TextInput::new("次数", &self.bench_runs)
+                            .padding(10)
+                            .on_input(Message::BenchRunsChanged),
+                    )
+                    .push(if self.bench.is_none() {
+                        bench.on_press(Message::BenchPressed)
+                    } else {
+                        bench
+                    })
+                    .push(
+                        TextInput::new("导出路径（不含扩展名）", &self.bench_path)
+                            .padding(10)
+                            .on_input(Message::BenchPathChanged),
+                    )
+                    .push(if self.bench_result.is_some() {
+                        csv.on_press(Message::ExportCsvPressed)
+                    } else {
+                        csv
+                    })
+                    .push(if self.bench_result.is_some() {
+                        svg.on_press(Message::ExportSvgPressed)
+                    } else {
+                        svg
+                    })
+                    .push(if self.bench_result.is_some() {
+                        png.on_press(Message::ExportPngPressed)
+                    } else {
+                        png
+                    })
+                    .push(Text::new(&self.bench_summary))
+            })
+            .push(match &self.bench_chart {
+                Some(chart) => Element::from(Image::<image::Handle>::new(chart.clone())),
+                None => Element::from(Column::new()),
+            })
+            .push(Text::new(i18n::tr(&self.key_length_error)))
+            .push(Text::new(i18n::tr(&self.mr_rounds_error)))
+            .push(Text::new(&self.estimate))
+            .push(Text::new(&self.keygen_progress))
+            .push(Text::new(&self.strength))
+            .push(Text::new(&self.fingerprint))
+            .push(Text::new(
+                self.error
+                    .lines()
+                    .map(i18n::tr)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ))
             .push(
                 Row::new()
                     .push(
@@ -284,6 +1376,7 @@ impl Sandbox for App {
                             .on_edit(Message::OutputChanged),
                     ),
             )
+            .push(Scrollable::new(Text::new(&self.work)))
             .into()
     }
 }
@@ -0,0 +1,175 @@
+use crate::encoding;
+
+const LINE_WIDTH: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArmorError {
+    /// 缺少 BEGIN/END 行或 CRC 行，通常是复制时被截断
+    Truncated,
+    /// CRC 校验不通过，内容在复制或传输中被改动
+    ChecksumMismatch,
+    /// 文本块的类型与期望不符，如把签名当作密文解密
+    UnexpectedKind,
+}
+
+impl ArmorError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            ArmorError::Truncated => "Armored block is truncated (missing checksum or END line)",
+            ArmorError::ChecksumMismatch => "Armor checksum mismatch, the data was corrupted",
+            ArmorError::UnexpectedKind => "Unexpected armored block type",
+        }
+    }
+}
+
+/// OpenPGP 的 CRC24（RFC 4880 第 6.1 节）
+pub fn crc24(data: &[u8]) -> u32 {
+    const CRC24_INIT: u32 = 0xb704ce;
+    const CRC24_POLY: u32 = 0x1864cfb;
+
+    let mut crc = CRC24_INIT;
+    for &b in data {
+        crc ^= (b as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+/// 与 OpenPGP 相同，CRC 对解码后的数据计算，而不是对 base64 文本
+fn checksum_line(data: &[u8]) -> String {
+    let crc = crc24(data).to_be_bytes();
+    format!("={}", encoding::base64_encode(&crc[1..]))
+}
+
+/// 把 data 以 base64 包装为带 CRC24 校验行的文本块，按 64 列折行
+pub fn armor(kind: &str, data: &[u8]) -> String {
+    let body: Vec<char> = encoding::base64_encode(data).chars().collect();
+    let mut lines = vec![format!("-----BEGIN {}-----", kind)];
+    lines.extend(
+        body.chunks(LINE_WIDTH)
+            .map(|l| l.iter().collect::<String>()),
+    );
+    lines.push(checksum_line(data));
+    lines.push(format!("-----END {}-----", kind));
+    lines.join("\n")
+}
+
+pub fn is_armored(text: &str) -> bool {
+    text.trim_start().starts_with("-----BEGIN ")
+}
+
+/// 依次解析 text 中的所有文本块，返回 (类型, 数据)，并校验每个块的 CRC。
+/// body 不是合法的 base64 时同样视为数据损坏
+pub fn dearmor_all(text: &str) -> Result<Vec<(String, Vec<u8>)>, ArmorError> {
+    let mut res = vec![];
+    let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    while let Some(begin) = lines.next() {
+        let kind = begin
+            .strip_prefix("-----BEGIN ")
+            .and_then(|l| l.strip_suffix("-----"))
+            .ok_or(ArmorError::Truncated)?;
+        let mut body = String::new();
+        let checksum = loop {
+            let line = lines.next().ok_or(ArmorError::Truncated)?;
+            if line.starts_with('=') {
+                break line;
+            }
+            if line.starts_with("-----") {
+                return Err(ArmorError::Truncated);
+            }
+            body.push_str(line);
+        };
+        if lines.next() != Some(format!("-----END {}-----", kind).as_str()) {
+            return Err(ArmorError::Truncated);
+        }
+        let data = encoding::base64_decode(&body).map_err(|_| ArmorError::ChecksumMismatch)?;
+        if checksum != checksum_line(&data) {
+            return Err(ArmorError::ChecksumMismatch);
+        }
+        res.push((kind.to_owned(), data));
+    }
+    Ok(res)
+}
+
+/// 解析恰好一个指定类型的文本块
+pub fn dearmor(kind: &str, text: &str) -> Result<Vec<u8>, ArmorError> {
+    let mut blocks = dearmor_all(text)?;
+    match blocks.pop() {
+        Some((k, data)) if blocks.is_empty() && k == kind => Ok(data),
+        Some(_) => Err(ArmorError::UnexpectedKind),
+        None => Err(ArmorError::Truncated),
+    }
+}
+
+/// 同 `dearmor`，内容为密文块、签名等文本，不是 UTF-8 时视为类型不符
+pub fn dearmor_text(kind: &str, text: &str) -> Result<String, ArmorError> {
+    String::from_utf8(dearmor(kind, text)?).map_err(|_| ArmorError::UnexpectedKind)
+}
+
+/// PEM（RFC 7468）：der 的 base64 按 64 列折行，没有校验行，可与 openssl 互通
+pub fn pem(kind: &str, der: &[u8]) -> String {
+    let chars: Vec<char> = encoding::base64_encode(der).chars().collect();
+    let mut lines = vec![format!("-----BEGIN {}-----", kind)];
+    lines.extend(
+        chars
+            .chunks(LINE_WIDTH)
+            .map(|l| l.iter().collect::<String>()),
+    );
+    lines.push(format!("-----END {}-----", kind));
+    lines.join("\n") + "\n"
+}
+
+/// 解析 text 中的第一个 PEM 块，返回 (类型, DER)
+pub fn depem(text: &str) -> Result<(String, Vec<u8>), &'static str> {
+    let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    let kind = lines
+        .find_map(|l| l.strip_prefix("-----BEGIN ")?.strip_suffix("-----"))
+        .ok_or(ArmorError::Truncated.message())?;
+    let end = format!("-----END {}-----", kind);
+    let mut body = String::new();
+    loop {
+        match lines.next() {
+            Some(line) if line == end => break,
+            Some(line) if !line.starts_with("-----") => body.push_str(line),
+            _ => return Err(ArmorError::Truncated.message()),
+        }
+    }
+    Ok((kind.to_owned(), encoding::base64_decode(&body)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// CRC-24/OPENPGP 的校验值，空输入时为初始值
+    #[test]
+    fn crc24_known_answer() {
+        assert_eq!(crc24(b"123456789"), 0x21cf02);
+        assert_eq!(crc24(b""), 0xb704ce);
+    }
+
+    /// 校验行是对数据本身而不是对 base64 文本的 CRC
+    #[test]
+    fn armor_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let text = armor("TEST DATA", &data);
+        let crc = crc24(&data).to_be_bytes();
+        assert!(text.contains(&format!("\n={}\n", encoding::base64_encode(&crc[1..]))));
+        assert_eq!(dearmor("TEST DATA", &text).unwrap(), data);
+        assert_eq!(dearmor("OTHER", &text), Err(ArmorError::UnexpectedKind));
+
+        let truncated = text.lines().take(3).collect::<Vec<_>>().join("\n");
+        assert_eq!(dearmor("TEST DATA", &truncated), Err(ArmorError::Truncated));
+        let corrupted = text.replacen("AAEC", "AAED", 1);
+        assert_ne!(corrupted, text);
+        assert_eq!(
+            dearmor("TEST DATA", &corrupted),
+            Err(ArmorError::ChecksumMismatch)
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use crate::bigint::BigInt;
+
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_SEQUENCE: u8 = 0x30;
+
+const INVALID: &str = "Invalid DER encoding";
+
+/// 一个 TLV：长度小于 128 时用短格式，否则先以一字节给出长度本身的字节数
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut res = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        res.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        res.push(0x80 | (bytes.len() - skip) as u8);
+        res.extend_from_slice(&bytes[skip..]);
+    }
+    res.extend_from_slice(content);
+    res
+}
+
+/// 非负整数，最高位为 1 时补一个 0 字节，以免被当作负数
+pub fn encode_integer(x: &BigInt) -> Vec<u8> {
+    let mut content = x.to_bytes_be();
+    if content.is_empty() || content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    encode(TAG_INTEGER, &content)
+}
+
+pub fn encode_sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    encode(TAG_SEQUENCE, &items.concat())
+}
+
+/// DER 解码器，依次读出 data 中的各个 TLV
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// 读出下一个 TLV 的内容，tag 须与期望的相同。拒绝不定长格式与非最短的长度编码
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8], &'static str> {
+        let [t, l, rest @ ..] = self.data else {
+            return Err(INVALID);
+        };
+        if *t != tag {
+            return Err(INVALID);
+        }
+        let (len, rest) = if l & 0x80 == 0 {
+            (*l as usize, rest)
+        } else {
+            let n = (l & 0x7f) as usize;
+            if n == 0 || n > std::mem::size_of::<usize>() || rest.len() < n || rest[0] == 0 {
+                return Err(INVALID);
+            }
+            let len = rest[..n].iter().fold(0, |len, &b| len << 8 | b as usize);
+            if len < 0x80 {
+                return Err(INVALID);
+            }
+            (len, &rest[n..])
+        };
+        if rest.len() < len {
+            return Err(INVALID);
+        }
+        let (content, rest) = rest.split_at(len);
+        self.data = rest;
+        Ok(content)
+    }
+
+    /// 非负整数，拒绝负数与多余的前导 0 字节
+    pub fn read_integer(&mut self) -> Result<BigInt, &'static str> {
+        let content = self.read(TAG_INTEGER)?;
+        match content {
+            [] => Err(INVALID),
+            [b, ..] if b & 0x80 != 0 => Err(INVALID),
+            [0, b, ..] if b & 0x80 == 0 => Err(INVALID),
+            _ => Ok(BigInt::from_bytes_be(content)),
+        }
+    }
+
+    pub fn read_sequence(&mut self) -> Result<Reader<'a>, &'static str> {
+        Ok(Reader::new(self.read(TAG_SEQUENCE)?))
+    }
+
+    /// 确认已读完，没有多余的数据
+    pub fn finish(&self) -> Result<(), &'static str> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(INVALID)
+        }
+    }
+}
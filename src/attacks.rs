@@ -0,0 +1,93 @@
+use crate::algorithms;
+use crate::bigint::{BigInt, ONE, ZERO};
+
+/// e / n 的连分数展开的各个渐近分数 (k, d)
+fn convergents(e: &BigInt, n: &BigInt) -> Vec<(BigInt, BigInt)> {
+    let mut res = vec![];
+    let (mut num, mut den) = (e.clone(), n.clone());
+    // h_(i-2), h_(i-1) 与 k_(i-2), k_(i-1)
+    let (mut h0, mut h1) = (ZERO.clone(), ONE.clone());
+    let (mut k0, mut k1) = (ONE.clone(), ZERO.clone());
+    while !den.is_zero() {
+        let (a, r) = num.div_rem(&den);
+        let h = &(&a * &h1) + &h0;
+        let k = &(&a * &k1) + &k0;
+        res.push((h.clone(), k.clone()));
+        (h0, h1) = (h1, h);
+        (k0, k1) = (k1, k);
+        (num, den) = (den, r);
+    }
+    res
+}
+
+/// 由 φ(n) 的候选值还原 p, q：它们是 x^2 - (n - φ + 1)x + n = 0 的两个整数根
+fn factor_from_phi(n: &BigInt, phi: &BigInt) -> Option<(BigInt, BigInt)> {
+    let s = (n + &ONE).checked_sub(phi)?;
+    let disc = (&s * &s).checked_sub(&n.mul_u64(4))?;
+    let root = disc.isqrt();
+    if &root * &root != disc || s.bit(0) != root.bit(0) {
+        return None;
+    }
+    let p = &(&s - &root) >> 1usize;
+    let q = &(&s + &root) >> 1usize;
+    (&p * &q == *n && p > ONE).then_some((p, q))
+}
+
+/// Wiener 攻击：d < n^0.25 / 3 且 q < p < 2q 时，k / d 是 e / n 连分数展开的某个渐近分数，
+/// 其中 ed = 1 + kφ(n)。逐个检验渐近分数，成功时返回 d
+pub fn wiener_attack(n: &BigInt, e: &BigInt) -> Option<BigInt> {
+    let ed_minus_1 = |d: &BigInt| (e * d).checked_sub(&ONE);
+    for (k, d) in convergents(e, n) {
+        if k.is_zero() || d.is_zero() {
+            continue;
+        }
+        let Some(x) = ed_minus_1(&d) else {
+            continue;
+        };
+        let (phi, r) = x.div_rem(&k);
+        if !r.is_zero() {
+            continue;
+        }
+        if factor_from_phi(n, &phi).is_some() {
+            return Some(d);
+        }
+    }
+    None
+}
+
+/// 共模攻击：同一明文 m 在同一 n 下以互素的 e1, e2 加密为 c1, c2 时，
+/// 由扩展欧几里得算法求出 a * e1 + b * e2 = 1，则 m = c1^a * c2^b mod n。
+/// 这里 a = e1^-1 mod e2 > 0，b = (1 - a * e1) / e2 <= 0，以 c2 的逆元计算 c2^b
+pub fn common_modulus(
+    c1: &BigInt,
+    c2: &BigInt,
+    e1: &BigInt,
+    e2: &BigInt,
+    n: &BigInt,
+) -> Result<BigInt, &'static str> {
+    let a = e1
+        .mod_inverse(e2)
+        .ok_or("e1 and e2 must be coprime for the common modulus attack")?;
+    let minus_b = &(&(&a * e1) - &ONE) / e2;
+    let c2_inv = c2.mod_inverse(n).ok_or("c2 is not invertible modulo n")?;
+    Ok(&(&c1.mod_pow(&a, n) * &c2_inv.mod_pow(&minus_b, n)) % n)
+}
+
+/// Håstad 广播攻击：同一明文 m 以相同的小指数 e 发给 e 个接收者，(c_i, n_i) 为各自的密文与模数。
+/// 由中国剩余定理得到 m^e mod (n_1 ... n_e)，m^e 小于模数之积，开 e 次方即得 m
+pub fn hastad_broadcast(ciphertexts: &[(BigInt, BigInt)]) -> Result<BigInt, &'static str> {
+    let me = algorithms::checked_crt(ciphertexts).ok_or("Moduli must be pairwise coprime")?;
+    let e = ciphertexts.len() as u32;
+    let m = me.nth_root(e);
+    if m.pow(e) != me {
+        return Err("Recovered value is not a perfect power, not enough ciphertexts");
+    }
+    Ok(m)
+}
+
+/// 小指数攻击：m^e < n 时加密不会发生模约简，c 就是 m^e，直接开 e 次方即得 m。
+/// c 不是完全 e 次幂时说明发生了约简，为 None
+pub fn low_exponent(c: &BigInt, e: u32) -> Option<BigInt> {
+    let m = c.nth_root(e);
+    (m.pow(e) == *c).then_some(m)
+}
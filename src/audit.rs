@@ -0,0 +1,140 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bigint::BigInt;
+use crate::encoding;
+use crate::entropy;
+use crate::hash;
+use crate::utils;
+
+/// 默认关闭，由 CLI 的 --audit 或 GUI 的复选框开启
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn path() -> PathBuf {
+    utils::home_file(".thss-rsa-audit.log")
+}
+
+/// 日志中的一条记录，对应文件中以制表符分隔的一行
+pub struct Record {
+    /// UNIX 时间，单位秒
+    pub time: u64,
+    pub operation: String,
+    pub key_fingerprint: String,
+    pub input_hash: String,
+    pub status: String,
+    /// 单位 us
+    pub duration: u128,
+    /// 参与生成随机数的熵源，以逗号分隔，没有用到随机数时为 -
+    pub sources: String,
+}
+
+impl Record {
+    fn fmt(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.time,
+            self.operation,
+            self.key_fingerprint,
+            self.input_hash,
+            self.status,
+            self.duration,
+            self.sources
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut sp: Vec<&str> = line.split('\t').collect();
+        // 早期的记录没有熵源一列
+        if sp.len() == 6 {
+            sp.push("-");
+        }
+        let [time, operation, key_fingerprint, input_hash, status, duration, sources] = sp[..]
+        else {
+            return None;
+        };
+        Some(Self {
+            time: time.parse().ok()?,
+            operation: operation.to_owned(),
+            key_fingerprint: key_fingerprint.to_owned(),
+            input_hash: input_hash.to_owned(),
+            status: status.to_owned(),
+            duration: duration.parse().ok()?,
+            sources: sources.to_owned(),
+        })
+    }
+}
+
+/// 模数 SHA-256 的前 8 个字节
+pub fn fingerprint(n: &BigInt) -> String {
+    encoding::hex_encode(&hash::sha256(&n.to_bytes_be())[..8])
+}
+
+/// 只记录输入的摘要，不记录输入本身
+fn input_hash(input: &[u8]) -> String {
+    encoding::hex_encode(&hash::sha256(input)[..8])
+}
+
+/// 开启审计时，向日志末尾追加一条记录；日志写入失败不影响操作本身。
+/// 熵源取自上一条记录以来实际用到的来源
+pub fn log(operation: &str, n: Option<&BigInt>, input: &[u8], status: &str, duration: u128) {
+    let sources = entropy::take_used();
+    if !is_enabled() {
+        return;
+    }
+    let record = Record {
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        operation: operation.to_owned(),
+        key_fingerprint: n.map(fingerprint).unwrap_or_else(|| String::from("-")),
+        input_hash: input_hash(input),
+        status: status.to_owned(),
+        duration,
+        sources: if sources.is_empty() {
+            String::from("-")
+        } else {
+            sources.join(",")
+        },
+    };
+    let _ = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path())
+        .and_then(|mut f| writeln!(f, "{}", record.fmt()));
+}
+
+/// 读取全部记录，跳过无法解析的行
+pub fn read() -> io::Result<Vec<Record>> {
+    match fs::read_to_string(path()) {
+        Ok(s) => Ok(s.lines().filter_map(Record::parse).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// 以对齐的表格形式显示记录
+pub fn render(records: &[Record]) -> String {
+    let mut lines = vec![format!(
+        "{:<12}{:<10}{:<18}{:<18}{:<10}{:<14}{}",
+        "time", "operation", "key", "input", "status", "duration(us)", "entropy"
+    )];
+    for r in records {
+        lines.push(format!(
+            "{:<12}{:<10}{:<18}{:<18}{:<10}{:<14}{}",
+            r.time, r.operation, r.key_fingerprint, r.input_hash, r.status, r.duration, r.sources
+        ));
+    }
+    lines.join("\n")
+}
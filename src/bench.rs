@@ -0,0 +1,182 @@
+use std::fs;
+use std::io;
+
+use crate::png::Canvas;
+
+/// SVG 与 PNG 图表共用的尺寸
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 360.0;
+const MARGIN: f64 = 60.0;
+
+/// PNG 调色板中的颜色：背景、坐标轴与文字、柱子、平均值
+const PALETTE: [[u8; 3]; 4] = [[255, 255, 255], [0, 0, 0], [70, 130, 180], [255, 0, 0]];
+const BLACK: u8 = 1;
+const STEELBLUE: u8 = 2;
+const RED: u8 = 3;
+
+/// 标题来自命令行参数等外部输入，放进 SVG 前须转义
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// 一组计时结果，单位 us
+#[derive(Clone)]
+pub struct BenchResult {
+    pub title: String,
+    pub times: Vec<u128>,
+}
+
+impl BenchResult {
+    pub fn new(title: String) -> Self {
+        Self {
+            title,
+            times: vec![],
+        }
+    }
+    pub fn mean(&self) -> f64 {
+        if self.times.is_empty() {
+            return 0.0;
+        }
+        self.times.iter().sum::<u128>() as f64 / self.times.len() as f64
+    }
+
+    /// 原始数据，每次运行一行
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec![String::from("run,time_us")];
+        for (i, t) in self.times.iter().enumerate() {
+            lines.push(format!("{},{}", i, t));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// 柱状图的几何：纵轴的最大值与每根柱子所占的宽度
+    fn layout(&self) -> (f64, f64) {
+        let max = self.times.iter().copied().max().unwrap_or(1).max(1) as f64;
+        let slot = (WIDTH - 2.0 * MARGIN) / self.times.len().max(1) as f64;
+        (max, slot)
+    }
+
+    fn y_of(max: f64, t: f64) -> f64 {
+        HEIGHT - MARGIN - t / max * (HEIGHT - 2.0 * MARGIN)
+    }
+
+    /// 每次运行一根柱子的柱状图，虚线为平均值
+    pub fn to_svg(&self) -> String {
+        let (max, slot) = self.layout();
+        let mut svg = vec![
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#,
+                WIDTH, HEIGHT
+            ),
+            format!(
+                r#"<text x="{}" y="{}" text-anchor="middle" font-size="16">{}</text>"#,
+                WIDTH / 2.0,
+                MARGIN / 2.0,
+                xml_escape(&self.title)
+            ),
+            format!(
+                r#"<line x1="{m}" y1="{b}" x2="{r}" y2="{b}" stroke="black"/><line x1="{m}" y1="{m}" x2="{m}" y2="{b}" stroke="black"/>"#,
+                m = MARGIN,
+                r = WIDTH - MARGIN,
+                b = HEIGHT - MARGIN
+            ),
+            format!(
+                r#"<text x="{}" y="{}" text-anchor="end">{}us</text>"#,
+                MARGIN - 4.0,
+                MARGIN + 4.0,
+                max
+            ),
+        ];
+        for (i, t) in self.times.iter().enumerate() {
+            let x = MARGIN + i as f64 * slot;
+            let y = Self::y_of(max, *t as f64);
+            svg.push(format!(
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="steelblue"/>"#,
+                x + slot * 0.1,
+                y,
+                slot * 0.8,
+                HEIGHT - MARGIN - y
+            ));
+            svg.push(format!(
+                r#"<text x="{:.1}" y="{}" text-anchor="middle">{}</text>"#,
+                x + slot / 2.0,
+                HEIGHT - MARGIN + 16.0,
+                i
+            ));
+        }
+        let mean_y = Self::y_of(max, self.mean());
+        svg.push(format!(
+            r#"<line x1="{}" y1="{:.1}" x2="{}" y2="{:.1}" stroke="red" stroke-dasharray="4"/>"#,
+            MARGIN,
+            mean_y,
+            WIDTH - MARGIN,
+            mean_y
+        ));
+        svg.push(format!(
+            r#"<text x="{}" y="{:.1}" text-anchor="end" fill="red">mean {:.1}us</text>"#,
+            WIDTH - MARGIN,
+            mean_y - 4.0,
+            self.mean()
+        ));
+        svg.push(String::from("</svg>"));
+        svg.join("\n") + "\n"
+    }
+
+    /// 与 `to_svg` 相同的柱状图，文字以内置的点阵字体绘制
+    pub fn to_png(&self) -> Vec<u8> {
+        let (max, slot) = self.layout();
+        let px = |v: f64| v.round() as i64;
+        let (m, r, b) = (px(MARGIN), px(WIDTH - MARGIN), px(HEIGHT - MARGIN));
+        let mut canvas = Canvas::new(WIDTH as usize, HEIGHT as usize, &PALETTE);
+
+        let title_w = Canvas::text_width(&self.title);
+        canvas.text(
+            px(WIDTH / 2.0) - title_w / 2,
+            px(MARGIN / 2.0),
+            &self.title,
+            BLACK,
+        );
+        canvas.fill_rect(m, b, r - m + 1, 1, BLACK);
+        canvas.fill_rect(m, m, 1, b - m + 1, BLACK);
+        let label = format!("{}us", max);
+        // 点阵字体比 SVG 的字宽，较长的刻度从左边缘开始，而不是被裁掉
+        let label_x = (m - 4 - Canvas::text_width(&label)).max(2);
+        canvas.text(label_x, m + 4, &label, BLACK);
+        for (i, t) in self.times.iter().enumerate() {
+            let x = MARGIN + i as f64 * slot;
+            let y = px(Self::y_of(max, *t as f64));
+            canvas.fill_rect(
+                px(x + slot * 0.1),
+                y,
+                px(slot * 0.8).max(1),
+                b - y,
+                STEELBLUE,
+            );
+            let label = i.to_string();
+            let label_x = px(x + slot / 2.0) - Canvas::text_width(&label) / 2;
+            canvas.text(label_x, b + 16, &label, BLACK);
+        }
+        let mean_y = px(Self::y_of(max, self.mean()));
+        // 与 SVG 的 stroke-dasharray="4" 相同，实线与空白各 4 像素
+        for x in (m..r).step_by(8) {
+            canvas.fill_rect(x, mean_y, 4.min(r - x), 1, RED);
+        }
+        let label = format!("mean {:.1}us", self.mean());
+        canvas.text(r - Canvas::text_width(&label), mean_y - 4, &label, RED);
+        canvas.to_png()
+    }
+
+    pub fn save_csv(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+    pub fn save_svg(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_png())
+    }
+}
@@ -1,51 +1,99 @@
 use std::cmp;
+use std::fmt;
+use std::hash;
 use std::ops;
+use std::str;
 
-use rand::Rng;
+use crate::{algorithms, encoding, entropy};
+use smallvec::{smallvec, SmallVec};
 
-use crate::utils;
-use once_cell::sync::Lazy;
+/// 不超过 4 个 value 时存放在栈上，巴雷特约简中的大部分临时值都不需要分配
+pub type Limbs = SmallVec<[u64; 4]>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HexError {
+    /// 去掉前缀与空白后没有任何数字
+    Empty,
+    /// 含有十六进制数字与空白以外的字符，记录其在原字符串中的字节位置
+    InvalidChar(usize),
+    /// 超过 MAX_LEN 个 value
+    TooLong,
+}
+
+impl HexError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            HexError::Empty => "Hex string is empty",
+            HexError::InvalidChar(_) => "Invalid char in hex string",
+            HexError::TooLong => "Input is longer than 1024 * 64 bits",
+        }
+    }
+}
 
 #[derive(Clone, Debug, Eq)]
 pub struct BigInt {
-    pub value: Vec<u64>, // 从小到大
-    pub length: usize,
+    value: Limbs, // 从小到大
+    /// 有效的 value 个数，不超过 value.len()，除 0 外最高的 value 不为 0
+    length: usize,
 }
 
 impl BigInt {
-    pub const VALUE_LEN: u64 = 32;
-    pub const VALUE_MASK: u64 = (((1 as u64) << Self::VALUE_LEN as u64) - 1);
-    pub const MAX_LEN: usize = 2048;
+    pub const VALUE_LEN: u64 = 64;
+    pub const VALUE_MASK: u64 = u64::MAX;
+    /// 最多 65536 位
+    pub const MAX_LEN: usize = 1024;
+    /// 每个 value 的十六进制位数
+    const HEX_DIGITS: usize = Self::VALUE_LEN as usize / 4;
+    /// 每个 value 的字节数
+    pub const VALUE_BYTES: usize = Self::VALUE_LEN as usize / 8;
 
-    pub fn from_hex(hex: &str) -> Result<Self, &'static str> {
-        if hex.len() % 8 != 0 {
-            return Err("String length is not multiplication of 8");
+    /// 解析十六进制，可带 0x 前缀，不区分大小写，忽略其中的空白与换行，长度不限于 16 的倍数
+    pub fn from_hex(hex: &str) -> Result<Self, HexError> {
+        let trimmed = hex.trim_start();
+        let body = trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+            .unwrap_or(trimmed);
+        let offset = hex.len() - body.len();
+        let mut digits = Vec::with_capacity(body.len());
+        for (i, c) in body.char_indices() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let d = c.to_digit(16).ok_or(HexError::InvalidChar(offset + i))?;
+            digits.push(d as u64);
+        }
+        if digits.is_empty() {
+            return Err(HexError::Empty);
         }
 
-        let length = hex.len() / 8;
+        let length = digits.len().div_ceil(Self::HEX_DIGITS);
         if length > Self::MAX_LEN {
-            return Err("Input is longer than 2048 * 8 * 32 bits");
+            return Err(HexError::TooLong);
         }
         let mut res = Self::with_capacity(length + 1);
         res.length = length;
-
-        for (i, c) in hex.chars().enumerate() {
-            let value_idx = length - 1 - i / 8;
-            res.value[value_idx] *= 16;
-            res.value[value_idx] += match c {
-                '0'..='9' => c as u64 - '0' as u64,
-                'a'..='f' => c as u64 - 'a' as u64 + 10,
-                _ => return Err("Invalid char in hex string"),
-            }
+        for (i, chunk) in digits.rchunks(Self::HEX_DIGITS).enumerate() {
+            res.value[i] = chunk.iter().fold(0, |acc, d| acc << 4 | d);
         }
+        res.remove_front_zeros();
         Ok(res)
     }
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            value: vec![0; capacity],
+            value: smallvec![0; capacity],
             length: 1,
         }
     }
+    /// 由从低到高的 value 构造，去掉高位的 0；超过 MAX_LEN 个 value 时报错
+    pub fn from_limbs(limbs: &[u64]) -> Result<Self, &'static str> {
+        let len = limbs.iter().rposition(|v| *v != 0).map_or(0, |i| i + 1);
+        if len > Self::MAX_LEN {
+            return Err("Input is longer than 1024 * 64 bits");
+        }
+        Ok(Self::from_slice(&limbs[..len]))
+    }
+    /// 同 `from_limbs`，但超过 MAX_LEN 个 value 时丢弃最低的若干个，只保留高位的 MAX_LEN 个
     pub fn from_slice(slice: &[u64]) -> Self {
         let idx;
         if slice.len() > Self::MAX_LEN {
@@ -53,16 +101,41 @@ impl BigInt {
         } else {
             idx = 0;
         }
-        let value: Vec<u64> = slice[idx..].into();
+        if slice.len() == idx {
+            return Self::with_capacity(1);
+        }
+        let value = Limbs::from_slice(&slice[idx..]);
         let len = value.len();
-        Self { value, length: len }
+        let mut res = Self { value, length: len };
+        res.remove_front_zeros();
+        res
+    }
+    /// 编译期构造，limbs 从低到高，正好占满栈上的空间
+    pub const fn from_const(limbs: [u64; 4]) -> Self {
+        let mut length = 4;
+        while length > 1 && limbs[length - 1] == 0 {
+            length -= 1;
+        }
+        Self {
+            value: Limbs::from_const(limbs),
+            length,
+        }
+    }
+    /// 有效的 value，从低到高
+    pub fn limbs(&self) -> &[u64] {
+        &self.value[..self.length]
+    }
+    /// 有效的 value 个数，0 为 1
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.length
     }
     pub fn rand(length: usize) -> Self {
-        let mut rng = rand::thread_rng();
         Self {
-            value: (0..length)
-                .map(|i| {
-                    let val = rng.gen::<u32>() as u64;
+            value: entropy::random_words(length)
+                .into_iter()
+                .enumerate()
+                .map(|(i, val)| {
                     // 确保最高 value 至少为 1，符合长度要求
                     if i != length - 1 || val > 0 {
                         val
@@ -74,46 +147,515 @@ impl BigInt {
             length,
         }
     }
+    /// 恰好 bits 位的随机数，即最高位为 1，bits 为 0 时 panic
+    pub fn rand_bits(bits: usize) -> Self {
+        assert!(bits > 0, "bits must be positive");
+        let length = bits.div_ceil(Self::VALUE_LEN as usize);
+        let mut words = entropy::random_words(length);
+        let top_bits = bits % Self::VALUE_LEN as usize;
+        if top_bits > 0 {
+            words[length - 1] &= (1 << top_bits) - 1;
+        }
+        let mut res = Self::from_slice(&words);
+        res.set_bit(bits - 1, true);
+        res
+    }
+    /// [low, high) 中均匀分布的随机数，high <= low 时 panic。
+    /// 按 high - low - 1 的位长生成随机数，超出范围则重新生成，每次成功的概率不低于 1/2
+    pub fn rand_range(low: &Self, high: &Self) -> Self {
+        assert!(low < high, "empty range");
+        let max = &(high - low) - &ONE;
+        let bits = max.bit_length();
+        let length = cmp::max(1, bits.div_ceil(Self::VALUE_LEN as usize));
+        loop {
+            let mut x = Self::from_slice(&entropy::random_words(length));
+            let top_bits = bits % Self::VALUE_LEN as usize;
+            if top_bits > 0 {
+                x.value[length - 1] &= (1 << top_bits) - 1;
+            } else if bits == 0 {
+                x.value[0] = 0;
+            }
+            x.remove_front_zeros();
+            if x <= max {
+                return &x + low;
+            }
+        }
+    }
     pub fn is_zero(&self) -> bool {
         self.length == 1 && self.value[0] == 0
     }
-    pub fn bitlen(&self) -> usize {
-        (self.length - 1) * Self::VALUE_LEN as usize
-            + utils::count_bits(self.value[self.length - 1])
+    /// 最高非 0 位是第几位，0 的位长为 0
+    pub fn bit_length(&self) -> usize {
+        let len = self.significant_len();
+        (len - 1) * Self::VALUE_LEN as usize
+            + (u64::BITS - self.value[len - 1].leading_zeros()) as usize
     }
-    pub fn to_int(&self) -> Result<u64, &str> {
-        let mut res: u64 = 0;
-        for i in 0..self.length {
-            let val = self.value[i] * (1 << (i * Self::VALUE_LEN as usize)) as u64;
-            if std::u64::MAX - res < val {
-                return Err("overflow");
+    /// 末尾连续 0 的位数，0 的为 0
+    pub fn trailing_zeros(&self) -> usize {
+        match self.value[..self.length].iter().position(|&v| v != 0) {
+            Some(i) => i * Self::VALUE_LEN as usize + self.value[i].trailing_zeros() as usize,
+            None => 0,
+        }
+    }
+    /// 第 i 位（从 0 开始）是否为 1
+    pub fn bit(&self, i: usize) -> bool {
+        let idx = i / Self::VALUE_LEN as usize;
+        idx < self.length && self.value[idx] >> (i % Self::VALUE_LEN as usize) & 1 == 1
+    }
+    /// 第 lo 位起的 w 位，w 不超过 VALUE_LEN
+    pub fn bits(&self, lo: usize, w: usize) -> u64 {
+        let idx = lo / Self::VALUE_LEN as usize;
+        let off = lo % Self::VALUE_LEN as usize;
+        if idx >= self.length {
+            return 0;
+        }
+        let mut res = self.value[idx] >> off;
+        if off + w > Self::VALUE_LEN as usize && idx + 1 < self.length {
+            res |= self.value[idx + 1] << (Self::VALUE_LEN as usize - off);
+        }
+        if w < Self::VALUE_LEN as usize {
+            res &= (1 << w) - 1;
+        }
+        res
+    }
+    /// 从高到低按 w 位一组取出各位，供幂运算扫描指数。最低一组为第 0 到 w - 1 位，
+    /// 最高一组可能不足 w 位；0 没有任何一组
+    pub fn windows(&self, w: usize) -> Windows<'_> {
+        assert!(
+            (1..=Self::VALUE_LEN as usize).contains(&w),
+            "window size must be between 1 and 64"
+        );
+        Windows {
+            x: self,
+            w,
+            left: self.bit_length().div_ceil(w),
+        }
+    }
+    /// 设置第 i 位，必要时增加长度
+    pub fn set_bit(&mut self, i: usize, bit: bool) {
+        let idx = i / Self::VALUE_LEN as usize;
+        let mask = 1 << (i % Self::VALUE_LEN as usize);
+        if idx >= self.length {
+            if !bit {
+                return;
+            }
+            if self.value.len() <= idx {
+                self.value.resize(idx + 1, 0);
             }
-            res += val;
+            self.value[self.length..=idx].fill(0);
+            self.length = idx + 1;
+        }
+        if bit {
+            self.value[idx] |= mask;
+        } else {
+            self.value[idx] &= !mask;
+            self.remove_front_zeros();
         }
-        Ok(res)
     }
+    /// 第 start 到 end - 1 个 value 构成的数，去掉高位的 0
     pub fn clone_slice(&self, start: usize, end: usize) -> Self {
-        let new_value = self.value[start..end].into();
-        Self {
-            value: new_value,
-            length: end - start,
-        }
+        Self::from_slice(&self.value[start..end])
     }
+    /// 每个 value 补足 16 位的十六进制，与 `from_hex` 对应
     pub fn fmt_hex(&self) -> String {
-        self.value[0..self.length]
-            .iter()
-            .rev()
-            .map(|v| format!("{:08x}", v))
-            .collect::<Vec<_>>()
-            .join("")
+        format!("{:0width$x}", self, width = Self::HEX_DIGITS * self.length)
     }
     pub fn print_hex(&self) {
         println!("{}", self.fmt_hex());
     }
-    /// 巴雷特取模中的 m
-    pub fn barrett_m(&self) -> Self {
-        let k = 2 * self.bitlen();
-        &(&Self::from_slice(&[1]) << k as u64) / self
+    /// 大端字节序的 base64 表示
+    pub fn to_base64(&self) -> String {
+        encoding::base64_encode(&self.to_bytes_be())
+    }
+    pub fn from_base64(text: &str) -> Result<Self, &'static str> {
+        Ok(Self::from_bytes_be(&encoding::base64_decode(text)?))
+    }
+    /// 大端字节序的 base64url 表示，不带填充
+    pub fn to_base64url(&self) -> String {
+        encoding::base64url_encode(&self.to_bytes_be())
+    }
+    pub fn from_base64url(text: &str) -> Result<Self, &'static str> {
+        Ok(Self::from_bytes_be(&encoding::base64url_decode(text)?))
+    }
+    /// 从大端字节序构造
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        let mut value: Vec<u64> = bytes
+            .rchunks(Self::VALUE_BYTES)
+            .map(|c| c.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+            .collect();
+        if value.is_empty() {
+            value.push(0);
+        }
+        Self::from_slice(&value)
+    }
+    /// 转为大端字节序，不含前导 0（0 本身为一个字节）
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let bytes: Vec<u8> = self.value[0..self.length]
+            .iter()
+            .rev()
+            .flat_map(|v| v.to_be_bytes())
+            .skip_while(|b| *b == 0)
+            .collect();
+        if bytes.is_empty() {
+            vec![0]
+        } else {
+            bytes
+        }
+    }
+    /// 从小端字节序构造
+    pub fn from_bytes_le(bytes: &[u8]) -> Self {
+        let mut value: Vec<u64> = bytes
+            .chunks(Self::VALUE_BYTES)
+            .map(|c| c.iter().rev().fold(0, |acc, b| acc << 8 | *b as u64))
+            .collect();
+        if value.is_empty() {
+            value.push(0);
+        }
+        Self::from_slice(&value)
+    }
+    /// 转为小端字节序，不含高位的 0（0 本身为一个字节）
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes_be();
+        bytes.reverse();
+        bytes
+    }
+    /// 返回 (商, 余数)，除数为 0 时 panic
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        self.checked_div_rem(rhs).expect("Division by zero")
+    }
+    pub fn checked_div_rem(&self, rhs: &Self) -> Result<(Self, Self), &'static str> {
+        if rhs.is_zero() {
+            return Err("Division by zero");
+        }
+        Ok(mod_div(self, rhs))
+    }
+
+    /// 返回 (self - rhs, 是否借位)。借位时结果为 2^(VALUE_LEN * len) + self - rhs，
+    /// len 为两者中较长的长度
+    pub fn overflowing_sub(&self, rhs: &Self) -> (Self, bool) {
+        let length = cmp::max(self.length, rhs.length);
+        let mut res = Self::with_capacity(length + 1);
+        res.length = length;
+        let mut borrow: u64 = 0;
+
+        for i in 0..length {
+            let self_val = if i < self.length { self.value[i] } else { 0 };
+            let rhs_val = if i < rhs.length { rhs.value[i] } else { 0 };
+            (res.value[i], borrow) = sub_with_borrow(self_val, rhs_val, borrow);
+        }
+        res.remove_front_zeros();
+        (res, borrow == 1)
+    }
+    /// self < rhs 时为 None
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_sub(rhs) {
+            (res, false) => Some(res),
+            (_, true) => None,
+        }
+    }
+    /// 除数为 0 时为 None
+    pub fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        self.checked_div_rem(rhs).ok().map(|(q, _)| q)
+    }
+    /// 除数为 0 时为 None
+    pub fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+        self.checked_div_rem(rhs).ok().map(|(_, r)| r)
+    }
+
+    /// 低 bits 位按位取反，更高的位为 0
+    pub fn not(&self, bits: usize) -> Self {
+        let length = cmp::max(1, bits.div_ceil(Self::VALUE_LEN as usize));
+        let mut res = Self::with_capacity(length);
+        res.length = length;
+        for i in 0..length {
+            let val = if i < self.length { self.value[i] } else { 0 };
+            res.value[i] = !val & Self::VALUE_MASK;
+        }
+        let top_bits = bits % Self::VALUE_LEN as usize;
+        if bits == 0 {
+            res.value[0] = 0;
+        } else if top_bits > 0 {
+            res.value[length - 1] &= (1 << top_bits) - 1;
+        }
+        res.remove_front_zeros();
+        res
+    }
+
+    /// 逐个 value 做按位运算，较短的一方高位补 0
+    fn bitwise(&self, rhs: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        let length = cmp::max(self.length, rhs.length);
+        let mut res = Self::with_capacity(length);
+        res.length = length;
+        for i in 0..length {
+            let lhs_val = if i < self.length { self.value[i] } else { 0 };
+            let rhs_val = if i < rhs.length { rhs.value[i] } else { 0 };
+            res.value[i] = f(lhs_val, rhs_val);
+        }
+        res.remove_front_zeros();
+        res
+    }
+
+    /// 除以一个 value 大小的 d，返回 (商, 余数)，逐个 value 做一次 u128 除法。d 为 0 时 panic
+    pub fn div_rem_u64(&self, d: u64) -> (Self, u64) {
+        assert!(d != 0, "Division by zero");
+        let mut res = Self::with_capacity(self.length);
+        res.length = self.length;
+        let mut rem: u128 = 0;
+        for i in (0..self.length).rev() {
+            let cur = rem << Self::VALUE_LEN | self.value[i] as u128;
+            res.value[i] = (cur / d as u128) as u64;
+            rem = cur % d as u128;
+        }
+        res.remove_front_zeros();
+        (res, rem as u64)
+    }
+
+    /// 同 `div_rem_u64`，只求余数，不分配空间
+    pub fn rem_u64(&self, d: u64) -> u64 {
+        assert!(d != 0, "Division by zero");
+        self.value[..self.length]
+            .iter()
+            .rev()
+            .fold(0u128, |rem, v| {
+                (rem << Self::VALUE_LEN | *v as u128) % d as u128
+            }) as u64
+    }
+
+    /// self * m，不构造单个 value 的临时 BigInt，只走一遍进位
+    pub fn mul_u64(&self, m: u64) -> Self {
+        self.mul_add_u64(m, 0)
+    }
+
+    /// self * m + a，同样只走一遍进位
+    fn mul_add_u64(&self, m: u64, a: u64) -> Self {
+        let mut res = Self::with_capacity(self.length + 2);
+        res.length = self.length;
+        let mut extend = a;
+        for i in 0..self.length {
+            (res.value[i], extend) = mul_add(self.value[i], m, 0, extend);
+        }
+        if extend != 0 {
+            res.value[res.length] = extend;
+            res.length += 1;
+        }
+        res.remove_front_zeros();
+        res
+    }
+
+    /// 按 2 到 36 进制解析，字母不区分大小写
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, &'static str> {
+        if !(2..=36).contains(&radix) {
+            return Err("Radix must be between 2 and 36");
+        }
+        if s.is_empty() || !s.chars().all(|c| c.is_digit(radix)) {
+            return Err("Invalid digit for the given radix");
+        }
+        let (chunk_digits, _) = Self::radix_chunk(radix);
+        let mut res = Self::with_capacity(1);
+        // 首块取余下的位数，其后每块 chunk_digits 位
+        let mut start = 0;
+        let mut end = match s.len() % chunk_digits {
+            0 => chunk_digits,
+            l => l,
+        };
+        while start < s.len() {
+            let chunk = u64::from_str_radix(&s[start..end], radix).unwrap();
+            let base = (radix as u64).pow((end - start) as u32);
+            res = res.mul_add_u64(base, chunk);
+            if res.length > Self::MAX_LEN {
+                return Err("Input is longer than 1024 * 64 bits");
+            }
+            start = end;
+            end += chunk_digits;
+        }
+        Ok(res)
+    }
+
+    /// 以 2 到 36 进制显示，字母小写，radix 超出范围时 panic
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        assert!((2..=36).contains(&radix), "radix must be between 2 and 36");
+        let (chunk_digits, chunk) = Self::radix_chunk(radix);
+        // 从低到高每次取出一块，除最高块外都补足 chunk_digits 位
+        let mut digits = vec![];
+        let mut x = self.clone();
+        loop {
+            let (q, mut r) = x.div_rem_u64(chunk);
+            for _ in 0..chunk_digits {
+                digits.push(std::char::from_digit((r % radix as u64) as u32, radix).unwrap());
+                r /= radix as u64;
+            }
+            if q.is_zero() {
+                break;
+            }
+            x = q;
+        }
+        while digits.len() > 1 && digits.last() == Some(&'0') {
+            digits.pop();
+        }
+        digits.iter().rev().collect()
+    }
+
+    /// 一个 value 能放下的最多位数 k 及 radix^k
+    fn radix_chunk(radix: u32) -> (usize, u64) {
+        let mut k = 0;
+        let mut base: u64 = 1;
+        while let Some(next) = base.checked_mul(radix as u64) {
+            base = next;
+            k += 1;
+        }
+        (k, base)
+    }
+
+    /// self^exp mod modulus，自动选择约简方式：模数为奇数时用蒙哥马利约简，否则用巴雷特约简。
+    /// 对同一模数反复运算时，直接使用 `algorithms` 中的函数可以省去每次的预计算。modulus 为 0 时 panic
+    pub fn mod_pow(&self, exp: &Self, modulus: &Self) -> Self {
+        let base = self % modulus;
+        if *modulus == ONE {
+            return base;
+        }
+        match algorithms::MontgomeryContext::new(modulus) {
+            Ok(ctx) => ctx.mod_power(&base, exp),
+            Err(_) => algorithms::BarrettContext::new(modulus).mod_pow(&base, exp),
+        }
+    }
+
+    /// self^exp
+    pub fn pow(&self, exp: u32) -> Self {
+        let mut res = ONE.clone();
+        for i in (0..u32::BITS - exp.leading_zeros()).rev() {
+            res = &res * &res;
+            if exp >> i & 1 == 1 {
+                res = &res * self;
+            }
+        }
+        res
+    }
+
+    /// 向下取整的平方根
+    pub fn isqrt(&self) -> Self {
+        self.nth_root(2)
+    }
+
+    /// 向下取整的 k 次方根，k 为 0 时 panic。
+    /// 牛顿迭代 x' = ((k - 1)x + self / x^(k-1)) / k，从不小于根的 2 的幂开始单调递减
+    pub fn nth_root(&self, k: u32) -> Self {
+        assert!(k > 0, "zeroth root is undefined");
+        if self.is_zero() || k == 1 {
+            return self.clone();
+        }
+        let bits = self.bit_length().div_ceil(k as usize);
+        let mut x = &ONE << bits;
+        loop {
+            let mut y = &x * (k - 1) as u64;
+            y += &(self / &x.pow(k - 1));
+            let (y, _) = y.div_rem_u64(k as u64);
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+
+    /// 最大公约数，gcd(0, 0) = 0
+    pub fn gcd(&self, rhs: &Self) -> Self {
+        algorithms::lehmer_gcd(self, rhs)
+    }
+
+    /// 最小公倍数，任一方为 0 时为 0
+    pub fn lcm(&self, rhs: &Self) -> Self {
+        if self.is_zero() || rhs.is_zero() {
+            return ZERO.clone();
+        }
+        &(self / &self.gcd(rhs)) * rhs
+    }
+
+    /// 模 modulus 的逆元，不互素或 modulus 为 0 时为 None
+    pub fn mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        if modulus.is_zero() {
+            return None;
+        }
+        // u * self + v * modulus = gcd，gcd 为 1 时 u 即为逆元
+        let (g, u, _) = algorithms::extended_euclid(&(self % modulus), modulus);
+        if g != ONE {
+            return None;
+        }
+        Some(u.rem_euclid(modulus))
+    }
+
+    /// 把 x 复制到 self，空间足够时不重新分配
+    pub fn copy_from(&mut self, x: &Self) {
+        if self.value.len() < x.length {
+            self.value.resize(x.length, 0);
+        }
+        self.value[..x.length].copy_from_slice(&x.value[..x.length]);
+        self.value[x.length..].fill(0);
+        self.length = x.length;
+    }
+
+    /// self + rhs 写入 out，out 的空间足够时不分配
+    pub fn add_into(&self, rhs: &Self, out: &mut Self) {
+        out.copy_from(self);
+        *out += rhs;
+    }
+
+    /// self - rhs 写入 out，需要确保 self >= rhs
+    pub fn sub_into(&self, rhs: &Self, out: &mut Self) {
+        out.copy_from(self);
+        *out -= rhs;
+    }
+
+    /// self * rhs 写入 out。使用朴素乘法时 out 的空间足够即不分配；
+    /// 达到 Karatsuba 阈值后仍需临时空间，结果再复制到 out
+    pub fn mul_into(&self, rhs: &Self, out: &mut Self) {
+        let a = &self.value[..self.length];
+        let b = &rhs.value[..rhs.length];
+        if out.value.len() < a.len() + b.len() + 1 {
+            out.value.resize(a.len() + b.len() + 1, 0);
+        }
+        if cmp::min(a.len(), b.len()) < KARATSUBA_THRESHOLD {
+            out.value.fill(0);
+            mul_schoolbook(a, b, &mut out.value);
+            out.length = a.len() + b.len();
+            out.remove_front_zeros();
+        } else {
+            out.copy_from(&(self * rhs));
+        }
+    }
+
+    /// self * self。朴素乘法下交叉项只算一半再翻倍，Karatsuba 下三个子乘积都是平方，约省一半的乘法
+    pub fn square(&self) -> Self {
+        let mut res = Self::with_capacity(2 * self.length + 1);
+        self.square_into(&mut res);
+        res
+    }
+
+    /// 同 `square`，结果写入 out，空间复用方式同 `mul_into`
+    pub fn square_into(&self, out: &mut Self) {
+        let a = &self.value[..self.length];
+        if a.len() >= NTT_THRESHOLD {
+            out.copy_from(&(self * self));
+            return;
+        }
+        if a.len() >= KARATSUBA_THRESHOLD {
+            out.copy_from(&Self::from_slice(&square_karatsuba(a)));
+        } else {
+            if out.value.len() < 2 * a.len() + 1 {
+                out.value.resize(2 * a.len() + 1, 0);
+            }
+            out.value.fill(0);
+            square_schoolbook(a, &mut out.value);
+        }
+        out.length = 2 * a.len();
+        out.remove_front_zeros();
+    }
+
+    /// 去掉高位的 0 后的长度，0 的长度为 1
+    fn significant_len(&self) -> usize {
+        let mut len = self.length;
+        while len > 1 && self.value[len - 1] == 0 {
+            len -= 1;
+        }
+        len
     }
 
     fn remove_front_zeros(&mut self) {
@@ -123,29 +665,85 @@ impl BigInt {
     }
 }
 
+/// 比较时忽略高位的 0，未经规范化的数（如按块切分消息得到的数）也能正确比较
 impl cmp::PartialEq for BigInt {
     fn eq(&self, other: &Self) -> bool {
-        self.length == other.length && self.value[..self.length] == other.value[..self.length]
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+/// 与 `PartialEq` 一致，只对去掉高位 0 后的 value 求哈希
+impl hash::Hash for BigInt {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.value[..self.significant_len()].hash(state);
     }
 }
 
 impl cmp::PartialOrd for BigInt {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        if self.length != other.length {
-            return Some(cmp::Ord::cmp(&self.length, &other.length));
+        Some(self.cmp(other))
+    }
+}
+
+impl cmp::Ord for BigInt {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        let self_len = self.significant_len();
+        let other_len = other.significant_len();
+        if self_len != other_len {
+            return self_len.cmp(&other_len);
         }
-        for i in (0..self.length).rev() {
+        for i in (0..self_len).rev() {
             if self.value[i] != other.value[i] {
-                return Some(cmp::Ord::cmp(&self.value[i], &other.value[i]));
+                return self.value[i].cmp(&other.value[i]);
             }
         }
-        Some(cmp::Ordering::Equal)
+        cmp::Ordering::Equal
     }
 }
 
-impl cmp::Ord for BigInt {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        cmp::PartialOrd::partial_cmp(&self, &other).unwrap()
+/// 以十进制显示
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "", &self.to_str_radix(10))
+    }
+}
+
+/// 逐个 value 转为定长数字后拼接，去掉前导 0（0 本身为 "0"）
+fn fmt_values(x: &BigInt, fmt_value: impl Fn(u64) -> String) -> String {
+    let digits: String = x.value[..x.length]
+        .iter()
+        .rev()
+        .map(|v| fmt_value(*v))
+        .collect();
+    match digits.trim_start_matches('0') {
+        "" => String::from("0"),
+        s => s.to_owned(),
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "0x", &fmt_values(self, |v| format!("{:016x}", v)))
+    }
+}
+
+impl fmt::UpperHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "0x", &fmt_values(self, |v| format!("{:016X}", v)))
+    }
+}
+
+impl fmt::Binary for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(true, "0b", &fmt_values(self, |v| format!("{:064b}", v)))
+    }
+}
+
+/// 解析十进制字符串
+impl str::FromStr for BigInt {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_radix(s, 10)
     }
 }
 
@@ -155,7 +753,7 @@ impl ops::Add<&BigInt> for &BigInt {
         let res_length = std::cmp::max(self.length, rhs.length);
         let mut res = BigInt::with_capacity(res_length + 3);
         res.length = res_length;
-        let get_val = |i, length, val: &Vec<u64>| {
+        let get_val = |i, length, val: &Limbs| {
             if i < length {
                 val[i]
             } else {
@@ -166,9 +764,9 @@ impl ops::Add<&BigInt> for &BigInt {
         for i in 0..res.length {
             let self_val = get_val(i, self.length, &self.value);
             let rhs_val = get_val(i, rhs.length, &rhs.value);
-            let sum = res.value[i] + self_val + rhs_val;
-            res.value[i + 1] = sum >> BigInt::VALUE_LEN;
-            res.value[i] = sum & BigInt::VALUE_MASK;
+            let sum = res.value[i] as u128 + self_val as u128 + rhs_val as u128;
+            res.value[i + 1] = (sum >> BigInt::VALUE_LEN) as u64;
+            res.value[i] = sum as u64;
         }
         if res.value[res.length] > 0 {
             res.length += 1;
@@ -177,32 +775,67 @@ impl ops::Add<&BigInt> for &BigInt {
     }
 }
 
+/// 需保证 self >= rhs，否则结果同 `overflowing_sub` 中借位时的值
 impl ops::Sub<&BigInt> for &BigInt {
     type Output = BigInt;
     fn sub(self, rhs: &BigInt) -> Self::Output {
-        let mut res = BigInt::with_capacity(self.length + 1);
-        res.length = self.length;
-        let mut borrow: u64 = 0;
+        self.overflowing_sub(rhs).0
+    }
+}
 
+impl ops::AddAssign<&BigInt> for BigInt {
+    fn add_assign(&mut self, rhs: &BigInt) {
+        let length = cmp::max(self.length, rhs.length);
+        if self.value.len() < length + 1 {
+            self.value.resize(length + 1, 0);
+        }
+        // length 以上可能残留之前的值
+        for v in &mut self.value[self.length..=length] {
+            *v = 0;
+        }
+        let mut carry = 0;
+        for i in 0..length {
+            let rhs_val = if i < rhs.length { rhs.value[i] } else { 0 };
+            (self.value[i], carry) = add_with_carry(self.value[i], rhs_val, carry);
+        }
+        self.value[length] = carry;
+        self.length = if carry > 0 { length + 1 } else { length };
+    }
+}
+
+/// 与 `Sub` 相同，需要确保 self >= rhs
+impl ops::SubAssign<&BigInt> for BigInt {
+    fn sub_assign(&mut self, rhs: &BigInt) {
+        let mut borrow = 0;
         for i in 0..self.length {
-            let rhs_val;
-            if i < rhs.length {
-                rhs_val = rhs.value[i];
-            } else {
-                rhs_val = 0;
-            }
-            let self_val = self.value[i];
+            let rhs_val = if i < rhs.length { rhs.value[i] } else { 0 };
+            (self.value[i], borrow) = sub_with_borrow(self.value[i], rhs_val, borrow);
+        }
+        self.remove_front_zeros();
+    }
+}
 
-            if self_val < borrow || self_val - borrow < rhs_val {
-                res.value[i] = self_val + (1 << BigInt::VALUE_LEN) - borrow - rhs_val;
-                borrow = 1;
-            } else {
-                res.value[i] = self_val - borrow - rhs_val;
-                borrow = 0;
-            }
+/// 乘积不能与乘数共用 value，因此仍会分配一次
+impl ops::MulAssign<&BigInt> for BigInt {
+    fn mul_assign(&mut self, rhs: &BigInt) {
+        *self = &*self * rhs;
+    }
+}
+
+impl ops::MulAssign<u64> for BigInt {
+    fn mul_assign(&mut self, rhs: u64) {
+        if self.value.len() < self.length + 2 {
+            self.value.resize(self.length + 2, 0);
         }
-        res.remove_front_zeros();
-        res
+        let mut extend = 0;
+        for i in 0..self.length {
+            (self.value[i], extend) = mul_add(self.value[i], rhs, 0, extend);
+        }
+        if extend != 0 {
+            self.value[self.length] = extend;
+            self.length += 1;
+        }
+        self.remove_front_zeros();
     }
 }
 
@@ -210,97 +843,414 @@ impl ops::Mul<&BigInt> for &BigInt {
     type Output = BigInt;
     fn mul(self, rhs: &BigInt) -> Self::Output {
         if self.is_zero() || rhs.is_zero() {
-            return BigInt::with_capacity(1);
+            return ZERO.clone();
         }
 
-        let mut res = BigInt::with_capacity(self.length + rhs.length + 1);
-        res.length = self.length + rhs.length;
-        for i in 0..self.length {
-            for j in 0..rhs.length {
-                res.value[i + j] += self.value[i] * rhs.value[j];
-            }
-            for j in i..res.length {
-                res.value[j + 1] += res.value[j] >> BigInt::VALUE_LEN;
-                res.value[j] &= BigInt::VALUE_MASK;
-            }
+        // 一方只有一个 value 时，如巴雷特取模中较小的商，走标量乘法
+        if rhs.length == 1 {
+            return self.mul_u64(rhs.value[0]);
+        }
+        if self.length == 1 {
+            return rhs.mul_u64(self.value[0]);
         }
 
+        let a = &self.value[..self.length];
+        let b = &rhs.value[..rhs.length];
+        let mut res = BigInt {
+            value: if cmp::min(a.len(), b.len()) >= NTT_THRESHOLD {
+                Limbs::from_vec(mul_ntt(a, b))
+            } else if cmp::min(a.len(), b.len()) >= KARATSUBA_THRESHOLD {
+                Limbs::from_vec(mul_karatsuba(a, b))
+            } else {
+                let mut out = smallvec![0; a.len() + b.len() + 1];
+                mul_schoolbook(a, b, &mut out);
+                out
+            },
+            length: self.length + rhs.length,
+        };
         res.remove_front_zeros();
         res
     }
 }
 
-impl ops::Mul<u64> for &BigInt {
-    type Output = BigInt;
-    fn mul(self, rhs: u64) -> Self::Output {
-        if self.length == 0 || rhs == 0 {
-            return BigInt::with_capacity(1);
+/// 返回 (a + b + carry 的低位, 进位)，carry 为 0 或 1
+pub fn add_with_carry(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let sum = a as u128 + b as u128 + carry as u128;
+    (sum as u64, (sum >> BigInt::VALUE_LEN) as u64)
+}
+
+/// 返回 (a - b - borrow 的低位, 借位)，borrow 为 0 或 1
+pub fn sub_with_borrow(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let (res, b1) = a.overflowing_sub(b);
+    let (res, b2) = res.overflowing_sub(borrow);
+    (res, (b1 || b2) as u64)
+}
+
+/// 返回 (a * b + c + carry 的低位, 高位)，结果不超过 2^128 - 1，不会溢出
+pub fn mul_add(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let val = a as u128 * b as u128 + c as u128 + carry as u128;
+    (val as u64, (val >> BigInt::VALUE_LEN) as u64)
+}
+
+/// 两个乘数都不少于这么多个 value 时使用 Karatsuba 乘法
+const KARATSUBA_THRESHOLD: usize = 48;
+
+/// out += a * b
+fn mul_schoolbook(a: &[u64], b: &[u64], out: &mut [u64]) {
+    for i in 0..a.len() {
+        let mut carry: u64 = 0;
+        for j in 0..b.len() {
+            (out[i + j], carry) = mul_add(a[i], b[j], out[i + j], carry);
+        }
+        let mut k = i + b.len();
+        while carry != 0 {
+            (out[k], carry) = add_with_carry(out[k], carry, 0);
+            k += 1;
         }
+    }
+}
 
-        let mut res = BigInt::with_capacity(self.length + 2);
-        res.length = self.length;
-        let mut extend: u64 = 0;
-        for i in 0..self.length {
-            let val = self.value[i] * rhs + extend;
-            res.value[i] = val & BigInt::VALUE_MASK;
-            extend = val >> BigInt::VALUE_LEN;
+/// out = a * a，out 需全为 0 且至少 2 * a.len() + 1 个 value。
+/// 先算 i < j 的交叉项，左移一位翻倍后再加上对角项 a_i^2
+fn square_schoolbook(a: &[u64], out: &mut [u64]) {
+    for i in 0..a.len() {
+        let mut carry: u64 = 0;
+        for j in i + 1..a.len() {
+            (out[i + j], carry) = mul_add(a[i], a[j], out[i + j], carry);
         }
+        out[i + a.len()] = carry;
+    }
 
-        if extend != 0 {
-            res.value[res.length] = extend;
-            res.length += 1
+    let mut carry: u64 = 0;
+    for v in out[..2 * a.len()].iter_mut() {
+        let top = *v >> (BigInt::VALUE_LEN - 1);
+        *v = *v << 1 | carry;
+        carry = top;
+    }
+
+    let mut carry: u64 = 0;
+    for i in 0..a.len() {
+        let (lo, hi) = mul_add(a[i], a[i], 0, 0);
+        let c;
+        (out[2 * i], c) = add_with_carry(out[2 * i], lo, carry);
+        (out[2 * i + 1], carry) = add_with_carry(out[2 * i + 1], hi, c);
+    }
+}
+
+/// out += x，超出 out 范围的部分必须为 0
+fn add_limbs_into(out: &mut [u64], x: &[u64]) {
+    let mut carry: u64 = 0;
+    let mut i = 0;
+    while i < out.len() && (i < x.len() || carry != 0) {
+        (out[i], carry) = add_with_carry(out[i], x.get(i).copied().unwrap_or(0), carry);
+        i += 1;
+    }
+    debug_assert!(carry == 0 && x[cmp::min(i, x.len())..].iter().all(|v| *v == 0));
+}
+
+/// out -= x，需保证 out >= x
+fn sub_limbs_into(out: &mut [u64], x: &[u64]) {
+    let mut borrow: u64 = 0;
+    let mut i = 0;
+    while i < out.len() && (i < x.len() || borrow != 0) {
+        (out[i], borrow) = sub_with_borrow(out[i], x.get(i).copied().unwrap_or(0), borrow);
+        i += 1;
+    }
+}
+
+/// Karatsuba 乘法，返回 a.len() + b.len() + 1 个 value
+fn mul_karatsuba(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut out = vec![0; a.len() + b.len() + 1];
+    if cmp::min(a.len(), b.len()) < KARATSUBA_THRESHOLD {
+        mul_schoolbook(a, b, &mut out);
+        return out;
+    }
+
+    // 长度相差悬殊时，把长的一方切成与短的一方等长的若干块分别相乘
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if long.len() >= 2 * short.len() {
+        for (k, chunk) in long.chunks(short.len()).enumerate() {
+            add_limbs_into(&mut out[k * short.len()..], &mul_karatsuba(chunk, short));
         }
-        res.remove_front_zeros();
-        res
+        return out;
+    }
+
+    // (a1 B + a0)(b1 B + b0) = z2 B^2 + z1 B + z0，z1 = (a0 + a1)(b0 + b1) - z0 - z2
+    let half = long.len() / 2;
+    let (a0, a1) = a.split_at(half);
+    let (b0, b1) = b.split_at(half);
+    let z0 = mul_karatsuba(a0, b0);
+    let z2 = mul_karatsuba(a1, b1);
+    let mut sum_a = vec![0; cmp::max(a0.len(), a1.len()) + 1];
+    add_limbs_into(&mut sum_a, a0);
+    add_limbs_into(&mut sum_a, a1);
+    let mut sum_b = vec![0; cmp::max(b0.len(), b1.len()) + 1];
+    add_limbs_into(&mut sum_b, b0);
+    add_limbs_into(&mut sum_b, b1);
+    let mut z1 = mul_karatsuba(&sum_a, &sum_b);
+    sub_limbs_into(&mut z1, &z0);
+    sub_limbs_into(&mut z1, &z2);
+
+    add_limbs_into(&mut out, &z0);
+    add_limbs_into(&mut out[half..], &z1);
+    add_limbs_into(&mut out[2 * half..], &z2);
+    out
+}
+
+/// 同 `mul_karatsuba`，三个子乘积都是平方：z1 = (a0 + a1)^2 - z0 - z2
+fn square_karatsuba(a: &[u64]) -> Vec<u64> {
+    let mut out = vec![0; 2 * a.len() + 1];
+    if a.len() < KARATSUBA_THRESHOLD {
+        square_schoolbook(a, &mut out);
+        return out;
+    }
+
+    let half = a.len() / 2;
+    let (a0, a1) = a.split_at(half);
+    let z0 = square_karatsuba(a0);
+    let z2 = square_karatsuba(a1);
+    let mut sum = vec![0; a1.len() + 1];
+    add_limbs_into(&mut sum, a0);
+    add_limbs_into(&mut sum, a1);
+    let mut z1 = square_karatsuba(&sum);
+    sub_limbs_into(&mut z1, &z0);
+    sub_limbs_into(&mut z1, &z2);
+
+    add_limbs_into(&mut out, &z0);
+    add_limbs_into(&mut out[half..], &z1);
+    add_limbs_into(&mut out[2 * half..], &z2);
+    out
+}
+
+/// 两个乘数都不少于这么多个 value 时使用 NTT 乘法
+const NTT_THRESHOLD: usize = 512;
+/// NTT 在模素数 p = 2^64 - 2^32 + 1 下进行，p - 1 含因子 2^32，且 7 是原根
+const NTT_P: u64 = 0xffff_ffff_0000_0001;
+const NTT_G: u64 = 7;
+
+/// 利用 2^64 ≡ 2^32 - 1、2^96 ≡ -1 (mod p) 化简 x mod p
+fn ntt_reduce(x: u128) -> u64 {
+    let lo = x as u64;
+    let hi = (x >> 64) as u64;
+    let (hi_hi, hi_lo) = (hi >> 32, hi & 0xffff_ffff);
+    let (mut t, borrow) = lo.overflowing_sub(hi_hi);
+    if borrow {
+        // 借位即加了 2^64 ≡ 2^32 - 1，减回去
+        t = t.wrapping_sub(0xffff_ffff);
+    }
+    let (mut res, carry) = t.overflowing_add(hi_lo * 0xffff_ffff);
+    if carry {
+        res = res.wrapping_add(0xffff_ffff);
+    }
+    if res >= NTT_P {
+        res -= NTT_P;
+    }
+    res
+}
+
+fn ntt_mul(a: u64, b: u64) -> u64 {
+    ntt_reduce(a as u128 * b as u128)
+}
+
+fn ntt_pow(mut a: u64, mut e: u64) -> u64 {
+    let mut res = 1;
+    while e != 0 {
+        if e & 1 == 1 {
+            res = ntt_mul(res, a);
+        }
+        a = ntt_mul(a, a);
+        e >>= 1;
+    }
+    res
+}
+
+/// 原地迭代 NTT，a.len() 须为 2 的幂
+fn ntt(a: &mut [u64], invert: bool) {
+    let n = a.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let mut w_len = ntt_pow(NTT_G, (NTT_P - 1) / len as u64);
+        if invert {
+            w_len = ntt_pow(w_len, NTT_P - 2);
+        }
+        let mut twiddles = Vec::with_capacity(len / 2);
+        let mut w = 1;
+        for _ in 0..len / 2 {
+            twiddles.push(w);
+            w = ntt_mul(w, w_len);
+        }
+        for chunk in a.chunks_mut(len) {
+            let (lo, hi) = chunk.split_at_mut(len / 2);
+            for ((u, v), w) in lo.iter_mut().zip(hi.iter_mut()).zip(&twiddles) {
+                let x = *u;
+                let y = ntt_mul(*v, *w);
+                *u = if x >= NTT_P - y {
+                    x - (NTT_P - y)
+                } else {
+                    x + y
+                };
+                *v = if x >= y { x - y } else { x + (NTT_P - y) };
+            }
+        }
+        len <<= 1;
+    }
+
+    if invert {
+        let n_inv = ntt_pow(n as u64, NTT_P - 2);
+        for x in a.iter_mut() {
+            *x = ntt_mul(*x, n_inv);
+        }
+    }
+}
+
+/// 每个 value 拆成的 16 位数位个数
+const NTT_DIGITS: usize = BigInt::VALUE_LEN as usize / 16;
+
+/// NTT 乘法，返回 a.len() + b.len() + 1 个 value。
+/// 每个 value 拆成四个 16 位的数位，卷积的每一项不超过 数位个数 * 2^32，远小于 p
+fn mul_ntt(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let digits = |x: &[u64]| -> Vec<u64> {
+        x.iter()
+            .flat_map(|v| (0..NTT_DIGITS).map(move |k| v >> (16 * k) & 0xffff))
+            .collect()
+    };
+    let mut da = digits(a);
+    let size = (NTT_DIGITS * (a.len() + b.len())).next_power_of_two();
+    da.resize(size, 0);
+    ntt(&mut da, false);
+    // 平方时只需一次正变换
+    if std::ptr::eq(a, b) {
+        for x in da.iter_mut() {
+            *x = ntt_mul(*x, *x);
+        }
+    } else {
+        let mut db = digits(b);
+        db.resize(size, 0);
+        ntt(&mut db, false);
+        for (x, y) in da.iter_mut().zip(db) {
+            *x = ntt_mul(*x, y);
+        }
+    }
+    ntt(&mut da, true);
+
+    let mut out = vec![0; a.len() + b.len() + 1];
+    let mut carry: u64 = 0;
+    for (i, out) in out.iter_mut().enumerate() {
+        let mut limb = 0;
+        for k in 0..NTT_DIGITS {
+            let val = da.get(NTT_DIGITS * i + k).copied().unwrap_or(0) + carry;
+            limb |= (val & 0xffff) << (16 * k);
+            carry = val >> 16;
+        }
+        *out = limb;
+    }
+    out
+}
+
+impl ops::Mul<u64> for &BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: u64) -> Self::Output {
+        self.mul_u64(rhs)
+    }
+}
+
+impl ops::Mul<u64> for BigInt {
+    type Output = BigInt;
+    fn mul(mut self, rhs: u64) -> Self::Output {
+        self *= rhs;
+        self
     }
 }
 
+/// 除数为 0 时 panic，需要处理时用 `checked_div_rem`
 impl ops::Div<&BigInt> for &BigInt {
     type Output = BigInt;
     fn div(self, rhs: &BigInt) -> Self::Output {
-        let (res, _) = mod_div(self, rhs);
-        res
+        self.div_rem(rhs).0
     }
 }
 
-impl ops::Shr<u64> for &BigInt {
+/// 除数为 0 时 panic，需要处理时用 `checked_div_rem`
+impl ops::Rem<&BigInt> for &BigInt {
     type Output = BigInt;
-    fn shr(self, shift: u64) -> Self::Output {
-        let block_offset = (shift / BigInt::VALUE_LEN) as usize;
-        let value_offset = (shift % BigInt::VALUE_LEN) as usize;
+    fn rem(self, rhs: &BigInt) -> Self::Output {
+        self.div_rem(rhs).1
+    }
+}
+
+impl ops::BitAnd<&BigInt> for &BigInt {
+    type Output = BigInt;
+    fn bitand(self, rhs: &BigInt) -> Self::Output {
+        self.bitwise(rhs, |a, b| a & b)
+    }
+}
+
+impl ops::BitOr<&BigInt> for &BigInt {
+    type Output = BigInt;
+    fn bitor(self, rhs: &BigInt) -> Self::Output {
+        self.bitwise(rhs, |a, b| a | b)
+    }
+}
+
+impl ops::BitXor<&BigInt> for &BigInt {
+    type Output = BigInt;
+    fn bitxor(self, rhs: &BigInt) -> Self::Output {
+        self.bitwise(rhs, |a, b| a ^ b)
+    }
+}
+
+impl ops::Shr<usize> for &BigInt {
+    type Output = BigInt;
+    fn shr(self, shift: usize) -> Self::Output {
+        let block_offset = shift / BigInt::VALUE_LEN as usize;
+        let value_offset = (shift % BigInt::VALUE_LEN as usize) as u64;
+        if block_offset >= self.length {
+            return ZERO.clone();
+        }
         let mut res = BigInt::with_capacity(self.length - block_offset + 2);
         res.length = self.length - block_offset;
 
         for i in block_offset..self.length {
-            let mut next_value = if i + 1 < self.length {
-                self.value[i + 1] & ((1 << value_offset as u64) - 1)
+            // 移位量为 0 时不取相邻 value 的位，避免移出整个 value
+            let next_value = if i + 1 < self.length && value_offset > 0 {
+                self.value[i + 1] << (BigInt::VALUE_LEN - value_offset)
             } else {
                 0
             };
-            next_value =
-                (next_value << (BigInt::VALUE_LEN - value_offset as u64)) & BigInt::VALUE_MASK;
-            res.value[i - block_offset] = next_value + (self.value[i] >> value_offset as u64);
+            res.value[i - block_offset] = next_value | (self.value[i] >> value_offset);
         }
         res.remove_front_zeros();
         res
     }
 }
 
-impl ops::Shl<u64> for &BigInt {
+impl ops::Shl<usize> for &BigInt {
     type Output = BigInt;
-    fn shl(self, shift: u64) -> Self::Output {
-        let block_offset = (shift / BigInt::VALUE_LEN) as usize;
-        let value_offset = (shift % BigInt::VALUE_LEN) as usize;
+    fn shl(self, shift: usize) -> Self::Output {
+        let block_offset = shift / BigInt::VALUE_LEN as usize;
+        let value_offset = (shift % BigInt::VALUE_LEN as usize) as u64;
         let mut res = BigInt::with_capacity(self.length + block_offset + 6);
         res.length = self.length + block_offset;
 
         for i in block_offset..self.length + block_offset {
-            let this_value = self.value[i - block_offset]
-                & ((1 << (BigInt::VALUE_LEN - value_offset as u64)) - 1);
-            let next_value =
-                self.value[i - block_offset] >> (BigInt::VALUE_LEN - value_offset as u64);
-            res.value[i] |= (this_value << value_offset) & BigInt::VALUE_MASK;
-            res.value[i + 1] |= next_value;
+            let val = self.value[i - block_offset];
+            res.value[i] |= val << value_offset;
+            if value_offset > 0 {
+                res.value[i + 1] |= val >> (BigInt::VALUE_LEN - value_offset);
+            }
         }
         while res.value[res.length] > 0 {
             res.length += 1;
@@ -310,14 +1260,59 @@ impl ops::Shl<u64> for &BigInt {
     }
 }
 
+/// 原地右移，不分配新的空间
+impl ops::ShrAssign<usize> for BigInt {
+    fn shr_assign(&mut self, shift: usize) {
+        let block_offset = shift / BigInt::VALUE_LEN as usize;
+        let value_offset = (shift % BigInt::VALUE_LEN as usize) as u64;
+        if block_offset >= self.length {
+            self.value.fill(0);
+            self.length = 1;
+            return;
+        }
+        for i in block_offset..self.length {
+            let next_value = if i + 1 < self.length && value_offset > 0 {
+                self.value[i + 1] << (BigInt::VALUE_LEN - value_offset)
+            } else {
+                0
+            };
+            self.value[i - block_offset] = next_value | (self.value[i] >> value_offset);
+        }
+        self.value[self.length - block_offset..self.length].fill(0);
+        self.length -= block_offset;
+        self.remove_front_zeros();
+    }
+}
+
+impl ops::ShlAssign<usize> for BigInt {
+    fn shl_assign(&mut self, shift: usize) {
+        *self = &*self << shift;
+    }
+}
+
+impl ops::Shr<u64> for &BigInt {
+    type Output = BigInt;
+    fn shr(self, shift: u64) -> Self::Output {
+        self >> shift as usize
+    }
+}
+
+impl ops::Shl<u64> for &BigInt {
+    type Output = BigInt;
+    fn shl(self, shift: u64) -> Self::Output {
+        self << shift as usize
+    }
+}
+
 pub fn mod_div(x: &BigInt, y: &BigInt) -> (BigInt, BigInt) {
     if x >= y {
         let mut res = BigInt::with_capacity(x.length);
         let mut remain = x.clone_slice(x.length - y.length + 1, x.length);
 
         for i in (0..x.length - y.length + 1).rev() {
-            let mut lower: u64 = 0;
-            let mut upper = BigInt::VALUE_MASK;
+            // 在 u128 中二分，避免边界处溢出
+            let mut lower: u128 = 0;
+            let mut upper = BigInt::VALUE_MASK as u128;
 
             res.value[i] = 0;
             remain = &remain << BigInt::VALUE_LEN;
@@ -325,8 +1320,8 @@ pub fn mod_div(x: &BigInt, y: &BigInt) -> (BigInt, BigInt) {
 
             while lower <= upper {
                 let mid = (lower + upper) >> 1;
-                if y * mid <= remain {
-                    res.value[i] = mid;
+                if y * mid as u64 <= remain {
+                    res.value[i] = mid as u64;
                     lower = mid + 1;
                 } else {
                     upper = mid - 1;
@@ -343,6 +1338,223 @@ pub fn mod_div(x: &BigInt, y: &BigInt) -> (BigInt, BigInt) {
     }
 }
 
-pub static ONE: Lazy<BigInt> = Lazy::new(|| BigInt::from_slice(&[1]));
-pub static TWO: Lazy<BigInt> = Lazy::new(|| BigInt::from_slice(&[2]));
-pub static THREE: Lazy<BigInt> = Lazy::new(|| BigInt::from_slice(&[3]));
+/// `BigInt::windows` 返回的迭代器
+pub struct Windows<'a> {
+    x: &'a BigInt,
+    w: usize,
+    /// 还未取出的组数
+    left: usize,
+}
+
+impl Iterator for Windows<'_> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        Some(self.x.bits(self.left * self.w, self.w))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.left, Some(self.left))
+    }
+}
+
+impl ExactSizeIterator for Windows<'_> {}
+
+/// 带符号的大数，用于扩展欧几里得等会出现负数中间值的算法。0 总是非负
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedBigInt {
+    negative: bool,
+    magnitude: BigInt,
+}
+
+impl SignedBigInt {
+    pub fn new(negative: bool, magnitude: BigInt) -> Self {
+        Self {
+            negative: negative && !magnitude.is_zero(),
+            magnitude,
+        }
+    }
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+    pub fn magnitude(&self) -> &BigInt {
+        &self.magnitude
+    }
+    /// 模 m 的非负余数
+    pub fn rem_euclid(&self, m: &BigInt) -> BigInt {
+        let r = &self.magnitude % m;
+        if self.negative && !r.is_zero() {
+            m - &r
+        } else {
+            r
+        }
+    }
+}
+
+impl From<BigInt> for SignedBigInt {
+    fn from(magnitude: BigInt) -> Self {
+        Self::new(false, magnitude)
+    }
+}
+
+impl cmp::PartialOrd for SignedBigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl cmp::Ord for SignedBigInt {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => cmp::Ordering::Greater,
+            (true, false) => cmp::Ordering::Less,
+        }
+    }
+}
+
+impl fmt::Display for SignedBigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(!self.negative, "", &self.magnitude.to_str_radix(10))
+    }
+}
+
+impl ops::Neg for &SignedBigInt {
+    type Output = SignedBigInt;
+    fn neg(self) -> Self::Output {
+        SignedBigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl ops::Add<&SignedBigInt> for &SignedBigInt {
+    type Output = SignedBigInt;
+    fn add(self, rhs: &SignedBigInt) -> Self::Output {
+        if self.negative == rhs.negative {
+            return SignedBigInt::new(self.negative, &self.magnitude + &rhs.magnitude);
+        }
+        // 异号时由绝对值较大的一方决定符号
+        if self.magnitude >= rhs.magnitude {
+            SignedBigInt::new(self.negative, &self.magnitude - &rhs.magnitude)
+        } else {
+            SignedBigInt::new(rhs.negative, &rhs.magnitude - &self.magnitude)
+        }
+    }
+}
+
+impl ops::Sub<&SignedBigInt> for &SignedBigInt {
+    type Output = SignedBigInt;
+    fn sub(self, rhs: &SignedBigInt) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl ops::Mul<&SignedBigInt> for &SignedBigInt {
+    type Output = SignedBigInt;
+    fn mul(self, rhs: &SignedBigInt) -> Self::Output {
+        SignedBigInt::new(
+            self.negative != rhs.negative,
+            &self.magnitude * &rhs.magnitude,
+        )
+    }
+}
+
+/// 向 0 取整，与 Rust 整数除法相同；除数为 0 时 panic
+impl ops::Div<&SignedBigInt> for &SignedBigInt {
+    type Output = SignedBigInt;
+    fn div(self, rhs: &SignedBigInt) -> Self::Output {
+        SignedBigInt::new(
+            self.negative != rhs.negative,
+            &self.magnitude / &rhs.magnitude,
+        )
+    }
+}
+
+/// 余数与被除数同号，与 Rust 整数取余相同；除数为 0 时 panic
+impl ops::Rem<&SignedBigInt> for &SignedBigInt {
+    type Output = SignedBigInt;
+    fn rem(self, rhs: &SignedBigInt) -> Self::Output {
+        SignedBigInt::new(self.negative, &self.magnitude % &rhs.magnitude)
+    }
+}
+
+macro_rules! impl_primitive_conversions {
+    ($($t:ty),*) => {$(
+        impl From<$t> for BigInt {
+            fn from(x: $t) -> Self {
+                BigInt::from_bytes_be(&x.to_be_bytes())
+            }
+        }
+
+        /// 超出该类型的范围时返回 Err("overflow")
+        impl TryFrom<&BigInt> for $t {
+            type Error = &'static str;
+            fn try_from(x: &BigInt) -> Result<Self, Self::Error> {
+                if x.bit_length() > <$t>::BITS as usize {
+                    return Err("overflow");
+                }
+                let res = x.value[..x.length]
+                    .iter()
+                    .rev()
+                    .fold(0u128, |acc, v| acc << BigInt::VALUE_LEN | *v as u128);
+                Ok(res as $t)
+            }
+        }
+    )*};
+}
+
+impl_primitive_conversions!(u8, u16, u32, u64, u128);
+
+pub static ZERO: BigInt = BigInt::from_const([0; 4]);
+pub static ONE: BigInt = BigInt::from_const([1, 0, 0, 0]);
+pub static TWO: BigInt = BigInt::from_const([2, 0, 0, 0]);
+pub static THREE: BigInt = BigInt::from_const([3, 0, 0, 0]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schoolbook(a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut out = vec![0; a.len() + b.len() + 1];
+        mul_schoolbook(a, b, &mut out);
+        out
+    }
+
+    /// NTT 阈值附近的随机乘数（含长度不等、全 1 的最大值）与朴素乘法、Karatsuba 的结果一致
+    #[test]
+    fn ntt_matches_schoolbook_and_karatsuba() {
+        let lens = [
+            (NTT_THRESHOLD - 1, NTT_THRESHOLD),
+            (NTT_THRESHOLD, NTT_THRESHOLD),
+            (NTT_THRESHOLD + 1, NTT_THRESHOLD + 37),
+            (2 * NTT_THRESHOLD, NTT_THRESHOLD),
+            (BigInt::MAX_LEN, BigInt::MAX_LEN),
+        ];
+        for (la, lb) in lens {
+            let a = entropy::random_words(la);
+            let b = entropy::random_words(lb);
+            let expected = schoolbook(&a, &b);
+            assert_eq!(mul_karatsuba(&a, &b), expected);
+            assert_eq!(mul_ntt(&a, &b), expected, "{} x {} limbs", la, lb);
+            assert_eq!(mul_ntt(&a, &a), schoolbook(&a, &a), "{} limbs squared", la);
+        }
+        let max = vec![u64::MAX; BigInt::MAX_LEN];
+        assert_eq!(mul_ntt(&max, &max), schoolbook(&max, &max));
+    }
+
+    /// 乘法运算符与 `square` 越过阈值时结果不变
+    #[test]
+    fn mul_dispatch_around_ntt_threshold() {
+        // 积不能超过 MAX_LEN 个 value，两个乘数最长 NTT_THRESHOLD 个
+        for len in [NTT_THRESHOLD - 1, NTT_THRESHOLD] {
+            let a = entropy::random_words(len);
+            let b = entropy::random_words(len);
+            let (x, y) = (BigInt::from_slice(&a), BigInt::from_slice(&b));
+            let expected = |x: &[u64], y: &[u64]| BigInt::from_limbs(&schoolbook(x, y)).unwrap();
+            assert_eq!(&x * &y, expected(&a, &b));
+            assert_eq!(x.square(), expected(&a, &a));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::bigint::BigInt;
+use crate::{entropy, rsa, utils};
+
+/// rsa 与 algorithms 用到的大数运算。本程序的 `BigInt` 之外也可换用 num-bigint 的 `BigUint`，
+/// 用来对照结果与比较性能
+pub trait BigNum: Clone + PartialEq + Sized {
+    fn from_u64(v: u64) -> Self;
+    fn from_bytes_be(bytes: &[u8]) -> Self;
+    /// 大端字节序，不含前导 0（0 本身为一个字节）
+    fn to_bytes_be(&self) -> Vec<u8>;
+    fn mul(&self, rhs: &Self) -> Self;
+    fn mod_pow(&self, exp: &Self, modulus: &Self) -> Self;
+    /// 不互素或模数为 0 时为 None
+    fn mod_inverse(&self, modulus: &Self) -> Option<Self>;
+
+    /// 不超过 bits 位的随机数，随机数均取自 `entropy`，两种实现拿到的是同样的随机源
+    fn rand_bits(bits: usize) -> Self {
+        let words = entropy::random_words(bits.div_ceil(64));
+        let mut bytes: Vec<u8> = words.iter().rev().flat_map(|w| w.to_be_bytes()).collect();
+        let extra = bytes.len() * 8 - bits;
+        for b in bytes.iter_mut().take(extra / 8) {
+            *b = 0;
+        }
+        if extra % 8 > 0 {
+            bytes[extra / 8] &= 0xff >> (extra % 8);
+        }
+        Self::from_bytes_be(&bytes)
+    }
+}
+
+impl BigNum for BigInt {
+    fn from_u64(v: u64) -> Self {
+        BigInt::from(v)
+    }
+    fn from_bytes_be(bytes: &[u8]) -> Self {
+        BigInt::from_bytes_be(bytes)
+    }
+    fn to_bytes_be(&self) -> Vec<u8> {
+        BigInt::to_bytes_be(self)
+    }
+    fn mul(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+    fn mod_pow(&self, exp: &Self, modulus: &Self) -> Self {
+        BigInt::mod_pow(self, exp, modulus)
+    }
+    fn mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        BigInt::mod_inverse(self, modulus)
+    }
+}
+
+impl BigNum for num_bigint::BigUint {
+    fn from_u64(v: u64) -> Self {
+        num_bigint::BigUint::from(v)
+    }
+    fn from_bytes_be(bytes: &[u8]) -> Self {
+        num_bigint::BigUint::from_bytes_be(bytes)
+    }
+    fn to_bytes_be(&self) -> Vec<u8> {
+        num_bigint::BigUint::to_bytes_be(self)
+    }
+    fn mul(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+    fn mod_pow(&self, exp: &Self, modulus: &Self) -> Self {
+        self.modpow(exp, modulus)
+    }
+    fn mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        if *modulus == Self::from(0u32) {
+            return None;
+        }
+        self.modinv(modulus)
+    }
+}
+
+/// 在 N 上做一轮运算：x * y、x^e mod n、再以 d 解回、x 模 n 的逆元，结果均为大端字节
+fn round<N: BigNum>(n: &[u8], d: &[u8], x: &[u8], y: &[u8]) -> Vec<Vec<u8>> {
+    let (n, d, x, y) = (
+        N::from_bytes_be(n),
+        N::from_bytes_be(d),
+        N::from_bytes_be(x),
+        N::from_bytes_be(y),
+    );
+    let c = x.mod_pow(&N::from_u64(rsa::E), &n);
+    vec![
+        x.mul(&y).to_bytes_be(),
+        c.to_bytes_be(),
+        c.mod_pow(&d, &n).to_bytes_be(),
+        x.mod_inverse(&n)
+            .map(|v| v.to_bytes_be())
+            .unwrap_or_default(),
+    ]
+}
+
+/// 两种实现对照的结果，耗时单位 us
+pub struct Comparison {
+    pub ours: u128,
+    pub theirs: u128,
+    pub mismatches: usize,
+}
+
+/// 生成 rounds 对 key_len 位的密钥，在本程序的 BigInt 与 num-bigint 上做同样的运算，
+/// 逐项比较结果并分别累计耗时
+pub fn compare(key_len: usize, rounds: usize) -> Result<Comparison, &'static str> {
+    let mut res = Comparison {
+        ours: 0,
+        theirs: 0,
+        mismatches: 0,
+    };
+    for _ in 0..rounds {
+        let (pub_key, priv_key) = rsa::gen_keys(key_len)?;
+        let (n, d) = (pub_key.n().to_bytes_be(), priv_key.d().to_bytes_be());
+        let x = BigInt::rand_bits(key_len - 1).to_bytes_be();
+        let y = BigInt::rand_bits(key_len - 1).to_bytes_be();
+        let (t, ours) = utils::count_time(|| round::<BigInt>(&n, &d, &x, &y));
+        res.ours += t;
+        let (t, theirs) = utils::count_time(|| round::<num_bigint::BigUint>(&n, &d, &x, &y));
+        res.theirs += t;
+        res.mismatches += ours.iter().zip(&theirs).filter(|(a, b)| a != b).count();
+    }
+    Ok(res)
+}
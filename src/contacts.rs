@@ -0,0 +1,85 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::rsa::{self, PublicKey};
+use crate::utils;
+
+/// 通讯录中的一个联系人
+#[derive(Clone)]
+pub struct Contact {
+    pub name: String,
+    pub pub_key: PublicKey,
+}
+
+/// 持久化的通讯录，用于选择数字信封的收件人
+#[derive(Default)]
+pub struct Contacts {
+    pub list: Vec<Contact>,
+}
+
+fn path() -> PathBuf {
+    utils::home_file(".thss-rsa-contacts")
+}
+
+impl Contacts {
+    /// 每行形如 `<名称> <n,e>`，公钥为十六进制，名称可以含空格，无法解析的行被忽略
+    pub fn load() -> Self {
+        let mut contacts = Self::default();
+        let Ok(s) = fs::read_to_string(path()) else {
+            return contacts;
+        };
+        for line in s.lines() {
+            let Some((name, pub_key)) = line.trim().rsplit_once(' ') else {
+                continue;
+            };
+            if let Ok(pub_key) =
+                rsa::pub_key_from_str(pub_key).and_then(|(n, e)| PublicKey::new(&n, e))
+            {
+                let _ = contacts.add(name, pub_key);
+            }
+        }
+        contacts
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let lines: Vec<String> = self
+            .list
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} {},{:08x}\n",
+                    c.name,
+                    c.pub_key.n().fmt_hex(),
+                    c.pub_key.e()
+                )
+            })
+            .collect();
+        fs::write(path(), lines.concat())
+    }
+
+    /// 添加联系人，同名时替换原有的公钥
+    pub fn add(&mut self, name: &str, pub_key: PublicKey) -> Result<(), &'static str> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Contact name is empty");
+        }
+        let contact = Contact {
+            name: name.to_owned(),
+            pub_key,
+        };
+        match self.list.iter_mut().find(|c| c.name == name) {
+            Some(c) => *c = contact,
+            None => self.list.push(contact),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.list.retain(|c| c.name != name);
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Contact> {
+        self.list.iter().find(|c| c.name == name)
+    }
+}
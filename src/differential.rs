@@ -0,0 +1,241 @@
+use rand::Rng;
+use rustcrypto_rsa::hazmat::{rsa_decrypt, rsa_encrypt};
+use rustcrypto_rsa::pkcs1::DecodeRsaPrivateKey;
+use rustcrypto_rsa::pkcs8::DecodePublicKey;
+use rustcrypto_rsa::rand_core::OsRng;
+use rustcrypto_rsa::sha2::{Digest, Sha256};
+use rustcrypto_rsa::traits::PublicKeyParts;
+use rustcrypto_rsa::{BigUint, Oaep, Pkcs1v15Sign, Pss, RsaPrivateKey, RsaPublicKey};
+
+use crate::bigint::BigInt;
+use crate::padding::{self, HashAlgorithm};
+use crate::rsa::{KeyFormat, PrivateKey, PublicKey};
+use crate::{encoding, hash, rsa};
+
+/// OAEP 两边使用的 label，对方的 label 为字符串
+const OAEP_LABEL: &str = "difftest";
+
+fn to_biguint(x: &BigInt) -> BigUint {
+    BigUint::from_bytes_be(&x.to_bytes_be())
+}
+
+fn from_biguint(x: &BigUint) -> BigInt {
+    BigInt::from_bytes_be(&x.to_bytes_be())
+}
+
+/// 两边结果不一致的一处
+pub struct Divergence {
+    pub round: usize,
+    pub operation: &'static str,
+    pub block: usize,
+    pub ours: String,
+    pub theirs: String,
+}
+
+impl Divergence {
+    pub fn fmt(&self) -> String {
+        format!(
+            "round {} {} block {}: ours {} theirs {}",
+            self.round, self.operation, self.block, self.ours, self.theirs
+        )
+    }
+}
+
+/// 把本程序导出的 PEM 交给对方解析：公钥为 SPKI（PKCS#8），私钥为 PKCS#1，
+/// 这样 DER 编码本身也在比较之列。对方只接受两个素数、不超过 4096 位的密钥
+fn import_keys(
+    pub_key: &PublicKey,
+    priv_key: &PrivateKey,
+) -> Result<(RsaPublicKey, RsaPrivateKey), &'static str> {
+    let their_pub = RsaPublicKey::from_public_key_pem(&pub_key.to_pem(KeyFormat::Pkcs8))
+        .map_err(|_| "rsa crate rejected public key")?;
+    let their_priv = RsaPrivateKey::from_pkcs1_pem(&priv_key.to_pem(KeyFormat::Pkcs1)?)
+        .map_err(|_| "rsa crate rejected private key")?;
+    Ok((their_pub, their_priv))
+}
+
+/// 一方接受而另一方拒绝时记录下来
+fn compare_accepted(
+    res: &mut Vec<Divergence>,
+    round: usize,
+    operation: &'static str,
+    ours: bool,
+    theirs: bool,
+) {
+    let fmt = |accepted: bool| String::from(if accepted { "accepted" } else { "rejected" });
+    if ours != theirs {
+        res.push(Divergence {
+            round,
+            operation,
+            block: 0,
+            ours: fmt(ours),
+            theirs: fmt(theirs),
+        });
+    }
+}
+
+/// 比较两边的 OAEP 与 PSS：各自加密、签名后交给对方解密、验证，
+/// 以及 PKCS#1 v1.5 签名在对方完整的验证流程（而不只是模幂）中是否有效
+fn compare_schemes(
+    res: &mut Vec<Divergence>,
+    round: usize,
+    msg: &[u8],
+    keys: (&PublicKey, &PrivateKey),
+    their_keys: (&RsaPublicKey, &RsaPrivateKey),
+) -> Result<(), &'static str> {
+    let (pub_key, priv_key) = keys;
+    let (their_pub, their_priv) = their_keys;
+    let hashed = Sha256::digest(msg);
+    if hashed[..] != hash::sha256(msg) {
+        res.push(Divergence {
+            round,
+            operation: "sha256",
+            block: 0,
+            ours: encoding::hex_encode(&hash::sha256(msg)),
+            theirs: encoding::hex_encode(&hashed),
+        });
+    }
+
+    // OAEP 与 PSS 都至少需要 66 字节长的模数，OAEP 最多容纳 k - 66 字节
+    let k = rsa::modulus_bytes(pub_key.modulus());
+    let Some(max_len) = k.checked_sub(2 * hash::SHA256_LEN + 2) else {
+        return Ok(());
+    };
+    let msg = &msg[..msg.len().min(max_len)];
+    let cipher = rsa::encrypt_oaep(msg, pub_key, OAEP_LABEL.as_bytes())?;
+    let theirs = their_priv.decrypt(Oaep::new_with_label::<Sha256, _>(OAEP_LABEL), &cipher);
+    compare_accepted(
+        res,
+        round,
+        "oaep decrypt",
+        true,
+        theirs.is_ok_and(|m| m == msg),
+    );
+    let cipher = their_pub
+        .encrypt(
+            &mut OsRng,
+            Oaep::new_with_label::<Sha256, _>(OAEP_LABEL),
+            msg,
+        )
+        .map_err(|_| "rsa crate failed to encrypt")?;
+    let ours = rsa::decrypt_oaep(&cipher, priv_key, OAEP_LABEL.as_bytes());
+    compare_accepted(
+        res,
+        round,
+        "oaep encrypt",
+        ours.is_ok_and(|m| m == msg),
+        true,
+    );
+
+    let sign = rsa::sign_pss(msg, priv_key, rsa::PSS_SALT_LEN)?;
+    let theirs = their_pub.verify(Pss::new::<Sha256>(), &hashed, &sign);
+    compare_accepted(res, round, "pss verify", true, theirs.is_ok());
+    let sign = their_priv
+        .sign_with_rng(&mut OsRng, Pss::new::<Sha256>(), &hashed)
+        .map_err(|_| "rsa crate failed to sign")?;
+    let ours = rsa::ver_sign_pss(msg, &sign, pub_key, rsa::PSS_SALT_LEN);
+    compare_accepted(res, round, "pss sign", ours, true);
+    Ok(())
+}
+
+/// 比较一块，不一致时记录下来
+fn compare(
+    res: &mut Vec<Divergence>,
+    round: usize,
+    operation: &'static str,
+    block: usize,
+    ours: &BigInt,
+    theirs: &BigUint,
+) {
+    let theirs = from_biguint(theirs);
+    if *ours != theirs {
+        res.push(Divergence {
+            round,
+            operation,
+            block,
+            ours: ours.fmt_hex(),
+            theirs: theirs.fmt_hex(),
+        });
+    }
+}
+
+/// 与 RustCrypto 的 rsa crate 做差分测试：生成 rounds 对 key_len 位的密钥，
+/// 各用一条随机消息逐块比较加密、解密，并比较签名与验证签名、OAEP 与 PSS。
+/// 本程序的加密是不带填充的教科书 RSA，签名对 EMSA-PKCS1-v1_5 编码的 SHA-256 摘要做模幂，
+/// 因此对方使用 hazmat 中的原始运算处理同样的块与 EM
+pub fn run(key_len: usize, rounds: usize) -> Result<Vec<Divergence>, &'static str> {
+    let mut res = vec![];
+    for round in 0..rounds {
+        let (pub_key, priv_key) = rsa::gen_keys(key_len)?;
+        let n = pub_key.n();
+        let (their_pub, their_priv) = import_keys(&pub_key, &priv_key)?;
+        if to_biguint(n) != *their_pub.n() {
+            return Err("rsa crate imported a different modulus");
+        }
+
+        // 1 到 3 块长的消息，最后一块通常不满
+        let block_bytes = (n.len() - 1) * BigInt::VALUE_BYTES;
+        let msg_len = rand::thread_rng().gen_range(1..=3 * block_bytes);
+        let msg = random_string::generate(msg_len, random_string::charsets::ALPHANUMERIC);
+        let blocks = rsa::bytes_to_blocks(msg.as_bytes(), n.len() - 1);
+
+        let cipher = pub_key.encrypt(&msg);
+        for (i, (block, c)) in blocks.iter().zip(cipher.split(",")).enumerate() {
+            let c = BigInt::from_hex(c).map_err(|e| e.message())?;
+            let theirs =
+                rsa_encrypt(&their_pub, &to_biguint(block)).map_err(|_| "rsa_encrypt failed")?;
+            compare(&mut res, round, "encrypt", i, &c, &theirs);
+            let theirs = rsa_decrypt(Some(&mut OsRng), &their_priv, &to_biguint(&c))
+                .map_err(|_| "rsa_decrypt failed")?;
+            compare(&mut res, round, "decrypt", i, block, &theirs);
+        }
+        let plain = priv_key.decrypt(&cipher)?;
+        if plain != msg {
+            res.push(Divergence {
+                round,
+                operation: "decrypt",
+                block: 0,
+                ours: plain,
+                theirs: msg.clone(),
+            });
+        }
+
+        let sign = priv_key.sign(&msg)?;
+        let s = BigInt::from_hex(&sign).map_err(|e| e.message())?;
+        let em = padding::emsa_pkcs1_v15_encode(
+            HashAlgorithm::Sha256,
+            &hash::sha256(msg.as_bytes()),
+            n.bit_length().div_ceil(8),
+        )?;
+        let em = BigInt::from_bytes_be(&em);
+        let theirs = rsa_decrypt(Some(&mut OsRng), &their_priv, &to_biguint(&em))
+            .map_err(|_| "rsa_decrypt failed")?;
+        compare(&mut res, round, "sign", 0, &s, &theirs);
+        let theirs = rsa_encrypt(&their_pub, &to_biguint(&s)).map_err(|_| "rsa_encrypt failed")?;
+        compare(&mut res, round, "verify", 0, &em, &theirs);
+        // 对方要求签名恰为模数字节数长，补回十六进制中省略的前导 0 字节
+        let k = rsa::modulus_bytes(pub_key.modulus());
+        let mut sign_bytes = s.to_bytes_be();
+        sign_bytes.splice(0..0, vec![0; k.saturating_sub(sign_bytes.len())]);
+        let hashed = Sha256::digest(msg.as_bytes());
+        let theirs = their_pub.verify(Pkcs1v15Sign::new::<Sha256>(), &hashed, &sign_bytes);
+        compare_accepted(&mut res, round, "pkcs1v15 verify", true, theirs.is_ok());
+        if !pub_key.verify(&msg, &sign) {
+            res.push(Divergence {
+                round,
+                operation: "verify",
+                block: 0,
+                ours: String::from("rejected"),
+                theirs: String::from("accepted"),
+            });
+        }
+        compare_schemes(
+            &mut res,
+            round,
+            msg.as_bytes(),
+            (&pub_key, &priv_key),
+            (&their_pub, &their_priv),
+        )?;
+    }
+    Ok(res)
+}
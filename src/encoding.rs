@@ -0,0 +1,183 @@
+use crate::bigint::BigInt;
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// 标准 base64 编码（RFC 4648），带 `=` 填充
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let v = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                res.push(BASE64_CHARS[(v >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+/// 解码标准 base64，忽略其中的空白字符
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, &'static str> {
+    let chars: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if !chars.len().is_multiple_of(4) {
+        return Err("Base64 length is not multiplication of 4");
+    }
+    let mut res = Vec::with_capacity(chars.len() / 4 * 3);
+    for (i, chunk) in chars.chunks(4).enumerate() {
+        let last = i == chars.len() / 4 - 1;
+        let mut v: u32 = 0;
+        let mut pad = 0;
+        for &c in chunk {
+            let d = match c {
+                b'=' if last => {
+                    pad += 1;
+                    0
+                }
+                _ if pad > 0 => return Err("Invalid padding in base64 string"),
+                _ => BASE64_CHARS
+                    .iter()
+                    .position(|x| *x == c)
+                    .ok_or("Invalid char in base64 string")? as u32,
+            };
+            v = v << 6 | d;
+        }
+        if pad > 2 {
+            return Err("Invalid padding in base64 string");
+        }
+        res.extend_from_slice(&v.to_be_bytes()[1..4 - pad]);
+    }
+    Ok(res)
+}
+
+/// base64url 编码（RFC 4648 第 5 节），以 `-`、`_` 代替 `+`、`/`，不带填充，用于 JWK
+pub fn base64url_encode(data: &[u8]) -> String {
+    base64_encode(data)
+        .trim_end_matches('=')
+        .chars()
+        .map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// 解码不带填充的 base64url
+pub fn base64url_decode(text: &str) -> Result<Vec<u8>, &'static str> {
+    if text.contains(['+', '/', '=']) {
+        return Err("Invalid char in base64 string");
+    }
+    let mut text: String = text
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    while !text.len().is_multiple_of(4) {
+        text.push('=');
+    }
+    base64_decode(&text)
+}
+
+pub fn hex_encode(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 解码十六进制，忽略空白字符，允许大小写混用及奇数长度
+pub fn hex_decode(text: &str) -> Result<Vec<u8>, &'static str> {
+    let mut digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| {
+            c.to_digit(16)
+                .map(|d| d as u8)
+                .ok_or("Invalid char in hex string")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if !digits.len().is_multiple_of(2) {
+        digits.insert(0, 0);
+    }
+    Ok(digits.chunks(2).map(|d| d[0] << 4 | d[1]).collect())
+}
+
+/// 把数据看作大端序的整数，以 radix 进制表示
+pub fn radix_encode(data: &[u8], radix: u32) -> String {
+    BigInt::from_bytes_be(data).to_str_radix(radix)
+}
+
+/// 解码 radix 进制，忽略空白字符
+pub fn radix_decode(text: &str, radix: u32) -> Result<Vec<u8>, &'static str> {
+    let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    Ok(BigInt::from_str_radix(&digits, radix)?.to_bytes_be())
+}
+
+/// 命令行输入输出数据时使用的编码
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Hex,
+    /// 2 到 36 进制的整数
+    Radix(u32),
+    Base64,
+    /// 不带填充的 base64url
+    Base64Url,
+    Raw,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Self, &'static str> {
+        match name {
+            "hex" => Ok(Format::Hex),
+            "bin" => Ok(Format::Radix(2)),
+            "oct" => Ok(Format::Radix(8)),
+            "dec" => Ok(Format::Radix(10)),
+            "base36" => Ok(Format::Radix(36)),
+            "base64" => Ok(Format::Base64),
+            "base64url" => Ok(Format::Base64Url),
+            "raw" => Ok(Format::Raw),
+            _ => {
+                Err("Unknown format, expected hex, bin, oct, dec, base36, base64, base64url or raw")
+            }
+        }
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Format::Hex => hex_encode(data).into_bytes(),
+            Format::Radix(r) => radix_encode(data, *r).into_bytes(),
+            Format::Base64 => base64_encode(data).into_bytes(),
+            Format::Base64Url => base64url_encode(data).into_bytes(),
+            Format::Raw => data.to_vec(),
+        }
+    }
+
+    /// 编码为可打印的文本，raw 格式无法做到
+    pub fn encode_text(&self, data: &[u8]) -> Result<String, &'static str> {
+        match self {
+            Format::Hex => Ok(hex_encode(data)),
+            Format::Radix(r) => Ok(radix_encode(data, *r)),
+            Format::Base64 => Ok(base64_encode(data)),
+            Format::Base64Url => Ok(base64url_encode(data)),
+            Format::Raw => Err("Raw format can not be used for text output"),
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+        let text = || std::str::from_utf8(data).map_err(|_| "Input is not valid text");
+        match self {
+            Format::Hex => hex_decode(text()?),
+            Format::Radix(r) => radix_decode(text()?, *r),
+            Format::Base64 => base64_decode(text()?),
+            Format::Base64Url => base64url_decode(text()?),
+            Format::Raw => Ok(data.to_vec()),
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::sync::Mutex;
+
+use crate::hash;
+use once_cell::sync::Lazy;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+/// 用户额外提供的熵。系统随机数的输出总会与由它播种的流异或，因此混入只会增加而不会减少熵
+struct Pool {
+    seed: [u8; 32],
+    fed: usize,
+    user: Option<StdRng>,
+    /// 确定性模式下取代全部随机源的生成器，以及它在审计日志中的名称
+    rng: Option<(Box<dyn RngCore + Send>, &'static str)>,
+    /// 是否混入 CPU 硬件随机数
+    hardware: bool,
+    /// 上次取出以来实际参与生成的来源
+    used: Vec<&'static str>,
+    health: Health,
+}
+
+impl Pool {
+    fn mark_used(&mut self, source: &'static str) {
+        if !self.used.contains(&source) {
+            self.used.push(source);
+        }
+    }
+}
+
+/// x86 的 RDSEED/RDRAND 指令，运行时检测是否支持。
+/// 硬件随机数只与系统随机数异或混合，不单独使用，即使硬件有缺陷或后门也不会降低熵
+#[cfg(target_arch = "x86_64")]
+mod hardware {
+    use std::arch::x86_64::{_rdrand32_step, _rdseed32_step};
+
+    /// 指令暂时取不到数时的重试次数，Intel 建议 RDRAND 重试 10 次
+    const RETRIES: usize = 10;
+
+    #[target_feature(enable = "rdseed")]
+    unsafe fn rdseed_step() -> Option<u32> {
+        let mut v = 0;
+        (_rdseed32_step(&mut v) == 1).then_some(v)
+    }
+
+    #[target_feature(enable = "rdrand")]
+    unsafe fn rdrand_step() -> Option<u32> {
+        let mut v = 0;
+        (_rdrand32_step(&mut v) == 1).then_some(v)
+    }
+
+    pub fn available() -> Vec<&'static str> {
+        let mut res = vec![];
+        if is_x86_feature_detected!("rdseed") {
+            res.push("rdseed");
+        }
+        if is_x86_feature_detected!("rdrand") {
+            res.push("rdrand");
+        }
+        res
+    }
+
+    /// 优先使用直接来自熵源的 RDSEED，其次 RDRAND，返回 (随机数, 来源)
+    pub fn next_u32() -> Option<(u32, &'static str)> {
+        if is_x86_feature_detected!("rdseed") {
+            for _ in 0..RETRIES {
+                if let Some(v) = unsafe { rdseed_step() } {
+                    return Some((v, "rdseed"));
+                }
+            }
+        }
+        if is_x86_feature_detected!("rdrand") {
+            for _ in 0..RETRIES {
+                if let Some(v) = unsafe { rdrand_step() } {
+                    return Some((v, "rdrand"));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod hardware {
+    pub fn available() -> Vec<&'static str> {
+        vec![]
+    }
+
+    pub fn next_u32() -> Option<(u32, &'static str)> {
+        None
+    }
+}
+
+/// 以 SHA-256(seed || counter) 为输出的确定性随机数生成器，输出只取决于种子
+struct Drbg {
+    seed: [u8; 32],
+    counter: u64,
+    buf: Vec<u32>,
+}
+
+impl RngCore for Drbg {
+    fn next_u32(&mut self) -> u32 {
+        if self.buf.is_empty() {
+            let mut msg = self.seed.to_vec();
+            msg.extend_from_slice(&self.counter.to_be_bytes());
+            self.counter += 1;
+            self.buf = hash::sha256(&msg)
+                .chunks(4)
+                .rev()
+                .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                .collect();
+        }
+        self.buf.pop().unwrap()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (self.next_u32() as u64) << 32 | self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_bytes_via_u32(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// 每 4 字节取一个 32 位随机数，按小端字节序填入
+fn fill_bytes_via_u32(rng: &mut impl RngCore, dest: &mut [u8]) {
+    for chunk in dest.chunks_mut(4) {
+        let word = rng.next_u32().to_le_bytes();
+        chunk.copy_from_slice(&word[..chunk.len()]);
+    }
+}
+
+/// ChaCha20 的密钥流（RFC 8439 的分组函数，以 64 位分组计数器、nonce 为 0）作为确定性随机数生成器，
+/// 以 32 字节种子为密钥，同一种子总是给出同一串输出
+pub struct ChaCha20Rng {
+    key: [u32; 8],
+    counter: u64,
+    block: [u32; 16],
+    pos: usize,
+}
+
+impl ChaCha20Rng {
+    pub fn new(seed: [u8; 32]) -> Self {
+        let mut key = [0; 8];
+        for (k, c) in key.iter_mut().zip(seed.chunks(4)) {
+            *k = u32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+        }
+        Self {
+            key,
+            counter: 0,
+            block: [0; 16],
+            pos: 16,
+        }
+    }
+}
+
+/// "expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// ChaCha20 分组函数：10 次列轮与对角轮后加上初始状态
+pub fn chacha20_block(state: &[u32; 16]) -> [u32; 16] {
+    let mut s = *state;
+    for _ in 0..10 {
+        quarter_round(&mut s, 0, 4, 8, 12);
+        quarter_round(&mut s, 1, 5, 9, 13);
+        quarter_round(&mut s, 2, 6, 10, 14);
+        quarter_round(&mut s, 3, 7, 11, 15);
+        quarter_round(&mut s, 0, 5, 10, 15);
+        quarter_round(&mut s, 1, 6, 11, 12);
+        quarter_round(&mut s, 2, 7, 8, 13);
+        quarter_round(&mut s, 3, 4, 9, 14);
+    }
+    for (x, y) in s.iter_mut().zip(state) {
+        *x = x.wrapping_add(*y);
+    }
+    s
+}
+
+impl RngCore for ChaCha20Rng {
+    fn next_u32(&mut self) -> u32 {
+        if self.pos == self.block.len() {
+            let mut state = [0; 16];
+            state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+            state[4..12].copy_from_slice(&self.key);
+            state[12] = self.counter as u32;
+            state[13] = (self.counter >> 32) as u32;
+            self.block = chacha20_block(&state);
+            self.counter += 1;
+            self.pos = 0;
+        }
+        self.pos += 1;
+        self.block[self.pos - 1]
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (self.next_u32() as u64) << 32 | self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_bytes_via_u32(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+static POOL: Lazy<Mutex<Pool>> = Lazy::new(|| {
+    Mutex::new(Pool {
+        seed: [0; 32],
+        fed: 0,
+        user: None,
+        rng: None,
+        hardware: false,
+        used: vec![],
+        health: Health::default(),
+    })
+});
+
+/// 混入用户提供的熵，如种子文件内容、按键时间等
+pub fn add_entropy(data: &[u8]) {
+    let mut pool = POOL.lock().unwrap();
+    for &b in data {
+        let i = pool.fed % 32;
+        pool.seed[i] = pool.seed[i].rotate_left(3) ^ b;
+        pool.fed += 1;
+    }
+    pool.user = Some(StdRng::from_seed(pool.seed));
+}
+
+/// 生成 length 个 64 位随机数，每个由两个 32 位随机数拼成，每个 32 位随机数都经过连续自检
+pub fn random_words(length: usize) -> Vec<u64> {
+    let mut rng = rand::thread_rng();
+    let pool = &mut *POOL.lock().unwrap();
+    if let Some((rng, source)) = &mut pool.rng {
+        let mut next_u32 = || {
+            let val = rng.next_u32();
+            pool.health.feed(val);
+            val as u64
+        };
+        let res = (0..length).map(|_| next_u32() << 32 | next_u32()).collect();
+        let source = *source;
+        pool.mark_used(source);
+        return res;
+    }
+    pool.mark_used("os");
+    if pool.user.is_some() {
+        pool.mark_used("user");
+    }
+    let mut next_u32 = || {
+        let mut val = rng.gen::<u32>();
+        if let Some(user) = &mut pool.user {
+            val ^= user.gen::<u32>();
+        }
+        if pool.hardware {
+            if let Some((hw, source)) = hardware::next_u32() {
+                val ^= hw;
+                pool.mark_used(source);
+            }
+        }
+        pool.health.feed(val);
+        val as u64
+    };
+    (0..length).map(|_| next_u32() << 32 | next_u32()).collect()
+}
+
+/// len 字节的随机数
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut res: Vec<u8> = random_words(len.div_ceil(8))
+        .into_iter()
+        .flat_map(u64::to_be_bytes)
+        .collect();
+    res.truncate(len);
+    res
+}
+
+/// 是否混入 CPU 硬件随机数，由设置或 --hw-rng 开启
+pub fn set_hardware(enabled: bool) {
+    POOL.lock().unwrap().hardware = enabled;
+}
+
+/// 当前配置下生成新密钥会用到的来源
+pub fn sources() -> Vec<&'static str> {
+    let pool = POOL.lock().unwrap();
+    let mut res = vec!["os"];
+    if pool.user.is_some() {
+        res.push("user");
+    }
+    if pool.hardware {
+        res.extend(hardware::available());
+    }
+    res
+}
+
+/// 取出上次调用以来实际参与生成随机数的来源，并清空记录
+pub fn take_used() -> Vec<&'static str> {
+    std::mem::take(&mut POOL.lock().unwrap().used)
+}
+
+/// 在 f 执行期间，所有随机数都由 seed 确定性地生成，用于由口令派生密钥
+pub fn with_seed<T>(seed: [u8; 32], f: impl FnOnce() -> T) -> T {
+    let drbg = Drbg {
+        seed,
+        counter: 0,
+        buf: vec![],
+    };
+    with_source(Box::new(drbg), "passphrase", f)
+}
+
+/// 在 f 执行期间，所有随机数都取自 rng，不再混入系统、用户与硬件随机数
+pub fn with_rng<T>(rng: Box<dyn RngCore + Send>, f: impl FnOnce() -> T) -> T {
+    with_source(rng, "injected", f)
+}
+
+fn with_source<T>(rng: Box<dyn RngCore + Send>, source: &'static str, f: impl FnOnce() -> T) -> T {
+    POOL.lock().unwrap().rng = Some((rng, source));
+    let res = f();
+    POOL.lock().unwrap().rng = None;
+    res
+}
+
+/// 对 `random_words` 实际给出的随机数做 FIPS 140-2 风格的连续自检，
+/// 无论它们来自系统、用户、硬件还是注入的随机数源
+#[derive(Default)]
+struct Health {
+    last: Option<u32>,
+    window: Vec<u32>,
+    failure: Option<&'static str>,
+}
+
+/// 单比特频数与长游程每 20000 位检查一次
+const HEALTH_WINDOW_WORDS: usize = 625;
+
+impl Health {
+    fn feed(&mut self, val: u32) {
+        if self.failure.is_some() {
+            return;
+        }
+        if self.last == Some(val) {
+            self.failure = Some("Entropy source failed health check: repeated output");
+            return;
+        }
+        self.last = Some(val);
+        self.window.push(val);
+        if self.window.len() == HEALTH_WINDOW_WORDS {
+            self.failure = check_window(&self.window).err();
+            self.window.clear();
+        }
+    }
+}
+
+fn check_window(sample: &[u32]) -> Result<(), &'static str> {
+    const LONG_RUN: u32 = 26;
+
+    let ones: u32 = sample.iter().map(|w| w.count_ones()).sum();
+    if ones <= 9725 || ones >= 10275 {
+        return Err("Entropy source failed health check: monobit");
+    }
+
+    let mut run = 0;
+    let mut last = None;
+    for w in sample {
+        for i in 0..32 {
+            let bit = (w >> i) & 1;
+            if Some(bit) == last {
+                run += 1;
+            } else {
+                run = 1;
+                last = Some(bit);
+            }
+            if run >= LONG_RUN {
+                return Err("Entropy source failed health check: long run");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 开始一轮自检，清除此前的结果。自检只观察输出而不额外取数，因此不改变确定性模式下的随机数序列
+pub fn start_health_check() {
+    POOL.lock().unwrap().health = Health::default();
+}
+
+/// 自 `start_health_check` 以来生成的随机数是否通过了自检：连续重复、单比特频数与长游程
+pub fn health_check() -> Result<(), &'static str> {
+    match POOL.lock().unwrap().health.failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,84 @@
+use crate::aes::{self, KEY_LEN, NONCE_LEN};
+use crate::entropy;
+use crate::hash::SHA256_LEN;
+use crate::rsa::{self, PrivateKey, PublicKey};
+
+const MAGIC: &[u8; 4] = b"RSAE";
+const VERSION: u8 = 3;
+
+/// 数字信封：随机生成 AES-256-GCM 会话密钥加密数据，RSA 只以 RSAES-OAEP 为每个收件人加密这 32 字节的会话密钥。
+/// 格式为 `"RSAE" || 版本 || 收件人数（1 字节）|| 各收件人的条目 || nonce（12 字节）|| 密文 || 标签`，
+/// 每个条目为 `收件人公钥指纹（32 字节）|| k（2 字节大端）|| 加密的会话密钥（k 字节）`。
+/// 密文之前的部分整体作为 GCM 的附加数据，改动其中任何一处都无法通过认证
+pub fn seal(plain: &[u8], keys: &[PublicKey]) -> Result<Vec<u8>, &'static str> {
+    let count = u8::try_from(keys.len())
+        .ok()
+        .filter(|c| *c > 0)
+        .ok_or("Envelope needs 1 to 255 recipients")?;
+    let mut session_key = [0u8; KEY_LEN];
+    session_key.copy_from_slice(&entropy::random_bytes(KEY_LEN));
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&entropy::random_bytes(NONCE_LEN));
+
+    let mut res = MAGIC.to_vec();
+    res.push(VERSION);
+    res.push(count);
+    for key in keys {
+        let wrapped = rsa::encrypt_oaep(&session_key, key, MAGIC)?;
+        res.extend_from_slice(&key.fingerprint().0);
+        res.extend_from_slice(&(wrapped.len() as u16).to_be_bytes());
+        res.extend_from_slice(&wrapped);
+    }
+    res.extend_from_slice(&nonce);
+    let sealed = aes::gcm_encrypt(&session_key, &nonce, &res, plain);
+    res.extend_from_slice(&sealed);
+    Ok(res)
+}
+
+/// 一个收件人的条目：(公钥指纹, 加密的会话密钥)
+type Entry<'a> = (&'a [u8], &'a [u8]);
+
+/// 解析信封头部，返回各条目与附加数据的长度
+fn parse_header(blob: &[u8]) -> Result<(Vec<Entry<'_>>, usize), &'static str> {
+    let prefix_len = MAGIC.len() + 2;
+    if blob.len() < prefix_len || &blob[..MAGIC.len()] != MAGIC || blob[MAGIC.len()] != VERSION {
+        return Err("Invalid envelope");
+    }
+    let mut entries = vec![];
+    let mut pos = prefix_len;
+    for _ in 0..blob[MAGIC.len() + 1] {
+        let fingerprint = blob.get(pos..pos + SHA256_LEN).ok_or("Invalid envelope")?;
+        pos += SHA256_LEN;
+        let k = blob.get(pos..pos + 2).ok_or("Invalid envelope")?;
+        let k = u16::from_be_bytes([k[0], k[1]]) as usize;
+        pos += 2;
+        let wrapped = blob.get(pos..pos + k).ok_or("Invalid envelope")?;
+        pos += k;
+        entries.push((fingerprint, wrapped));
+    }
+    let aad_len = pos + NONCE_LEN;
+    if entries.is_empty() || blob.len() < aad_len + aes::TAG_LEN {
+        return Err("Invalid envelope");
+    }
+    Ok((entries, aad_len))
+}
+
+/// `seal` 的逆过程。依次以 key 尝试解开各条目中的会话密钥，指纹与 key 相同的条目优先；
+/// 格式不符、没有条目能以 key 解开或认证失败时出错
+pub fn open(blob: &[u8], key: &PrivateKey) -> Result<Vec<u8>, &'static str> {
+    let (mut entries, aad_len) = parse_header(blob)?;
+    let fingerprint = key.public_key().fingerprint();
+    entries.sort_by_key(|(f, _)| *f != fingerprint.0);
+    let session_key = entries
+        .iter()
+        .find_map(|(_, wrapped)| {
+            rsa::decrypt_oaep(wrapped, key, MAGIC)
+                .ok()
+                .and_then(|k| <[u8; KEY_LEN]>::try_from(k).ok())
+        })
+        .ok_or("Envelope is not addressed to this key")?;
+    let (aad, sealed) = blob.split_at(aad_len);
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&aad[aad_len - NONCE_LEN..]);
+    aes::gcm_decrypt(&session_key, &nonce, aad, sealed)
+}
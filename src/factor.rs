@@ -0,0 +1,436 @@
+/// 支持分解的最大位数，留出 u128 移位乘法所需的余量
+pub const MAX_BITS: usize = 124;
+
+const TRIAL_BOUND: u64 = 1 << 16;
+const SMALL_BASES: [u128; 20] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+];
+
+/// 在 u128 内计算 a * b mod m，要求 a, b < m < 2^127
+fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >> 64 == 0 && b >> 64 == 0 {
+        return a * b % m;
+    }
+    // 把 b 按每 s 位一块从高到低做移位加法，保证中间结果不溢出
+    let s = m.leading_zeros() - 1;
+    let mut shift = (128 - b.leading_zeros()).div_ceil(s) * s;
+    let mut res = 0;
+    while shift > 0 {
+        shift -= s;
+        let chunk = (b >> shift) & ((1 << s) - 1);
+        res = ((res << s) % m + a * chunk % m) % m;
+    }
+    res
+}
+
+fn pow_mod(mut a: u128, mut b: u128, m: u128) -> u128 {
+    let mut res = 1 % m;
+    a %= m;
+    while b > 0 {
+        if b & 1 == 1 {
+            res = mul_mod(res, a, m);
+        }
+        a = mul_mod(a, a, m);
+        b >>= 1;
+    }
+    res
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = 1 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// 以前 20 个素数为底的 Miller Rabin
+pub fn is_prime(n: u128) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in SMALL_BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'witness: for a in SMALL_BASES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn small_primes(bound: u64) -> Vec<u64> {
+    let mut sieve = vec![true; bound as usize];
+    let mut primes = vec![];
+    for i in 2..bound as usize {
+        if sieve[i] {
+            primes.push(i as u64);
+            for j in (i * i..bound as usize).step_by(i) {
+                sieve[j] = false;
+            }
+        }
+    }
+    primes
+}
+
+/// Pollard rho，使用 Brent 的环检测并成批计算 gcd
+pub fn pollard_rho(n: u128, max_iter: u64) -> Option<u128> {
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    for c in 1..16 {
+        let f = |x| (mul_mod(x, x, n) + c) % n;
+        let (mut x, mut y, mut ys) = (0, 2, 2);
+        let (mut r, mut q, mut g) = (1u64, 1, 1);
+        let mut iter = 0;
+        while g == 1 && iter < max_iter {
+            x = y;
+            for _ in 0..r {
+                y = f(y);
+            }
+            let mut k = 0;
+            while k < r && g == 1 {
+                ys = y;
+                for _ in 0..std::cmp::min(128, r - k) {
+                    y = f(y);
+                    q = mul_mod(q, x.max(y) - x.min(y), n);
+                }
+                g = gcd(q, n);
+                k += 128;
+            }
+            iter += r;
+            r *= 2;
+        }
+        if g == n {
+            // 批量 gcd 越过了因子，逐步回退
+            loop {
+                ys = f(ys);
+                g = gcd(x.max(ys) - x.min(ys), n);
+                if g > 1 {
+                    break;
+                }
+            }
+        }
+        if g == 1 {
+            return None;
+        }
+        if g < n {
+            return Some(g);
+        }
+        // g == n 时换一个多项式重试
+    }
+    None
+}
+
+/// Pollard p - 1，平滑上界为 bound
+pub fn pollard_p_minus_1(n: u128, bound: u64) -> Option<u128> {
+    let mut a = 2;
+    for p in small_primes(bound) {
+        let mut pk = p;
+        while pk * p <= bound {
+            pk *= p;
+        }
+        a = pow_mod(a, pk as u128, n);
+    }
+    let g = gcd(a + n - 1, n);
+    if g > 1 && g < n {
+        Some(g)
+    } else {
+        None
+    }
+}
+
+/// 费马分解，寻找 a^2 - n = b^2，当两个因子接近时很快
+pub fn fermat(n: u128, max_iter: u64) -> Option<u128> {
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    let mut a = isqrt(n);
+    if a * a < n {
+        a += 1;
+    }
+    for _ in 0..max_iter {
+        let b2 = a * a - n;
+        let b = isqrt(b2);
+        if b * b == b2 {
+            let f = a - b;
+            return if f > 1 { Some(f) } else { None };
+        }
+        a += 1;
+    }
+    None
+}
+
+/// 求 t 使 t^2 = n mod p，p 为奇素数且 n 为模 p 的二次剩余
+fn tonelli_shanks(n: u64, p: u64) -> u64 {
+    let pow = |a: u64, b: u64| pow_mod(a as u128, b as u128, p as u128) as u64;
+    let mul = |a: u64, b: u64| (a as u128 * b as u128 % p as u128) as u64;
+    let s = (p - 1).trailing_zeros();
+    let q = (p - 1) >> s;
+    let mut z = 2;
+    while pow(z, (p - 1) / 2) != p - 1 {
+        z += 1;
+    }
+    let (mut m, mut c, mut t, mut r) = (s, pow(z, q), pow(n, q), pow(n, q.div_ceil(2)));
+    while t != 1 {
+        let mut i = 0;
+        let mut tt = t;
+        while tt != 1 {
+            tt = mul(tt, tt);
+            i += 1;
+        }
+        let b = pow(c, 1 << (m - i - 1));
+        m = i;
+        c = mul(b, b);
+        t = mul(t, c);
+        r = mul(r, b);
+    }
+    r
+}
+
+struct Relation {
+    /// x + m
+    root: u128,
+    /// 因子基中各素数的指数，下标 0 表示 -1
+    exponents: Vec<u32>,
+}
+
+/// 单多项式二次筛法，Q(x) = (x + m)^2 - n，m = ceil(sqrt(n))
+pub fn quadratic_sieve(n: u128) -> Option<u128> {
+    const BLOCK: i64 = 1 << 16;
+    let bits = 128 - n.leading_zeros();
+    let bound = match bits {
+        0..=60 => 1000,
+        61..=80 => 4000,
+        81..=100 => 15000,
+        _ => 40000,
+    };
+
+    let m = {
+        let s = isqrt(n);
+        if s * s == n {
+            return Some(s);
+        }
+        s + 1
+    };
+
+    // 因子基：-1 以及使 n 为二次剩余的素数
+    let mut base: Vec<u64> = vec![];
+    let mut roots: Vec<(u64, u64)> = vec![];
+    for p in small_primes(bound) {
+        let np = (n % p as u128) as u64;
+        if np == 0 {
+            return Some(p as u128);
+        }
+        if p == 2 {
+            base.push(2);
+            roots.push((0, 0));
+        } else if pow_mod(np as u128, (p as u128 - 1) / 2, p as u128) == 1 {
+            let t = tonelli_shanks(np, p);
+            let mp = (m % p as u128) as u64;
+            base.push(p);
+            roots.push(((t + p - mp) % p, (2 * p - t - mp) % p));
+        }
+    }
+    let logs: Vec<u8> = base
+        .iter()
+        .map(|p| (64 - p.leading_zeros()) as u8)
+        .collect();
+    let cols = base.len() + 1;
+    let needed = cols + 16;
+
+    let mut relations: Vec<Relation> = vec![];
+    let mut sieve = vec![0u8; BLOCK as usize];
+    let mut block_idx: i64 = 0;
+    while relations.len() < needed {
+        // 交替向正负两个方向扩展筛区间
+        let start = if block_idx % 2 == 0 {
+            block_idx / 2 * BLOCK
+        } else {
+            -(block_idx / 2 + 1) * BLOCK
+        };
+        block_idx += 1;
+        if block_idx > 1 << 20 {
+            return None;
+        }
+
+        sieve.iter_mut().for_each(|v| *v = 0);
+        for (i, &p) in base.iter().enumerate().skip(1) {
+            let ip = p as i64;
+            let offset = start.rem_euclid(ip);
+            let (r1, r2) = roots[i];
+            for r in if r1 == r2 { vec![r1] } else { vec![r1, r2] } {
+                let mut j = (r as i64 - offset).rem_euclid(ip);
+                while j < BLOCK {
+                    sieve[j as usize] += logs[i];
+                    j += ip;
+                }
+            }
+        }
+
+        // |Q(x)| 约为 2|x|sqrt(n)，允许一定的误差以补偿未筛的素数幂与 2
+        let threshold = |x: i64| {
+            let q = 2 * (x.unsigned_abs() as u128 + 1) * m;
+            (128 - q.leading_zeros()).saturating_sub(logs[logs.len() - 1] as u32 + 4) as u8
+        };
+        for j in 0..BLOCK {
+            let x = start + j;
+            if sieve[j as usize] < threshold(x) || (x < 0 && x.unsigned_abs() as u128 >= m) {
+                continue;
+            }
+            let root = if x >= 0 {
+                m + x as u128
+            } else {
+                m - x.unsigned_abs() as u128
+            };
+            let sq = root * root;
+            let (mut q, negative) = if sq >= n {
+                (sq - n, false)
+            } else {
+                (n - sq, true)
+            };
+            if q == 0 {
+                return Some(root);
+            }
+            let mut exponents = vec![0; cols];
+            exponents[0] = negative as u32;
+            for (i, &p) in base.iter().enumerate() {
+                while q % p as u128 == 0 {
+                    q /= p as u128;
+                    exponents[i + 1] += 1;
+                }
+            }
+            if q == 1 {
+                relations.push(Relation { root, exponents });
+            }
+        }
+    }
+
+    // GF(2) 上的高斯消元，每行附带一个单位阵以追踪是哪些关系组合而成
+    let words = cols.div_ceil(64);
+    let hist_words = relations.len().div_ceil(64);
+    let mut rows: Vec<(Vec<u64>, Vec<u64>)> = relations
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let mut v = vec![0u64; words];
+            for (c, e) in r.exponents.iter().enumerate() {
+                if e % 2 == 1 {
+                    v[c / 64] |= 1 << (c % 64);
+                }
+            }
+            let mut h = vec![0u64; hist_words];
+            h[i / 64] |= 1 << (i % 64);
+            (v, h)
+        })
+        .collect();
+    let mut pivot_row = 0;
+    for c in 0..cols {
+        let found = (pivot_row..rows.len()).find(|&r| rows[r].0[c / 64] >> (c % 64) & 1 == 1);
+        let Some(r) = found else { continue };
+        rows.swap(pivot_row, r);
+        let pivot = rows[pivot_row].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != pivot_row && row.0[c / 64] >> (c % 64) & 1 == 1 {
+                row.0.iter_mut().zip(&pivot.0).for_each(|(a, b)| *a ^= b);
+                row.1.iter_mut().zip(&pivot.1).for_each(|(a, b)| *a ^= b);
+            }
+        }
+        pivot_row += 1;
+    }
+
+    // 消元后全零的行即为一组平方关系
+    for (v, h) in &rows[pivot_row..] {
+        debug_assert!(v.iter().all(|w| *w == 0));
+        let mut x = 1;
+        let mut exponents = vec![0; cols];
+        for (i, r) in relations.iter().enumerate() {
+            if h[i / 64] >> (i % 64) & 1 == 1 {
+                x = mul_mod(x, r.root % n, n);
+                exponents
+                    .iter_mut()
+                    .zip(&r.exponents)
+                    .for_each(|(a, b)| *a += b);
+            }
+        }
+        let mut y = 1;
+        for (i, &p) in base.iter().enumerate() {
+            y = mul_mod(y, pow_mod(p as u128, exponents[i + 1] as u128 / 2, n), n);
+        }
+        let g = gcd((x + n - y) % n, n);
+        if g > 1 && g < n {
+            return Some(g);
+        }
+    }
+    None
+}
+
+/// 依次尝试 Fermat、p - 1、rho 与二次筛法，找到 n 的一个非平凡因子
+fn split(n: u128) -> Option<(u128, &'static str)> {
+    if let Some(f) = fermat(n, 1 << 12) {
+        return Some((f, "fermat"));
+    }
+    if let Some(f) = pollard_p_minus_1(n, 1 << 12) {
+        return Some((f, "p-1"));
+    }
+    if let Some(f) = pollard_rho(n, 1 << 15) {
+        return Some((f, "rho"));
+    }
+    quadratic_sieve(n).map(|f| (f, "quadratic sieve"))
+}
+
+/// 完全分解 n，返回升序排列的素因子以及分离出它所用的方法
+pub fn factorize(mut n: u128) -> Result<Vec<(u128, &'static str)>, &'static str> {
+    if n == 0 {
+        return Err("Cannot factor 0");
+    }
+    let mut res = vec![];
+    for p in small_primes(TRIAL_BOUND) {
+        while n.is_multiple_of(p as u128) {
+            n /= p as u128;
+            res.push((p as u128, "trial division"));
+        }
+    }
+
+    let mut stack = vec![(n, "trial division")];
+    while let Some((m, method)) = stack.pop() {
+        if m == 1 {
+            continue;
+        }
+        if is_prime(m) {
+            res.push((m, method));
+            continue;
+        }
+        let (f, method) = split(m).ok_or("Failed to find a factor")?;
+        stack.push((f, method));
+        stack.push((m / f, method));
+    }
+    res.sort();
+    Ok(res)
+}
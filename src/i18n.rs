@@ -0,0 +1,542 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Zh,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::Zh];
+
+    pub fn from_name(name: &str) -> Result<Self, &'static str> {
+        match name.to_ascii_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "zh" => Ok(Lang::Zh),
+            _ => Err("Unknown language, expected en or zh"),
+        }
+    }
+
+    /// 按 LANG 环境变量选择，默认英文
+    pub fn from_env() -> Self {
+        match std::env::var("LANG") {
+            Ok(l) if l.starts_with("zh") => Lang::Zh,
+            _ => Lang::En,
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lang::En => write!(f, "English"),
+            Lang::Zh => write!(f, "中文"),
+        }
+    }
+}
+
+static LANG: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        1 => Lang::Zh,
+        _ => Lang::En,
+    }
+}
+
+/// 消息目录：(代码, 英文, 中文)。代码中的错误值仍是英文原文，显示前由 `tr` 查表翻译；
+/// E 开头为错误，S 开头为状态信息，`{}` 为 `trf` 的参数位置
+const CATALOG: &[(&str, &str, &str)] = &[
+    // 密钥与 RSA 运算
+    (
+        "E101",
+        "Key length must be between 256 and 8192",
+        "密钥长度必须在 256 到 8192 之间",
+    ),
+    (
+        "E102",
+        "Key length must be a multiple of 64",
+        "密钥长度必须是 64 的倍数",
+    ),
+    ("E103", "Key length must be a number", "密钥长度必须是数字"),
+    ("E104", "Passphrase must not be empty", "口令不能为空"),
+    (
+        "E115",
+        "Miller-Rabin rounds must be a positive number",
+        "Miller-Rabin 轮数必须是正整数",
+    ),
+    (
+        "E116",
+        "e is not invertible modulo phi(n)",
+        "e 模 φ(n) 不可逆",
+    ),
+    (
+        "E117",
+        "Unknown weakness, expected close or small-d",
+        "未知的弱点类型，应为 close 或 small-d",
+    ),
+    (
+        "E118",
+        "Thread count must be a positive number",
+        "线程数必须是正整数",
+    ),
+    (
+        "E119",
+        "Congruence modulus must be between 2 and 2^32",
+        "同余条件的模数必须在 2 到 2^32 之间",
+    ),
+    (
+        "E120",
+        "Residue must be coprime to the modulus",
+        "余数必须与模数互素",
+    ),
+    (
+        "E121",
+        "Congruence must be given as residue/modulus",
+        "同余条件应写作 余数/模数",
+    ),
+    (
+        "E122",
+        "Prime count must be between 2 and 8",
+        "素数个数必须在 2 到 8 之间",
+    ),
+    (
+        "E123",
+        "Key length is too short for this many primes",
+        "密钥长度对于这么多个素数过短",
+    ),
+    (
+        "E124",
+        "Unknown key format, expected pkcs1 or pkcs8",
+        "未知的密钥格式，应为 pkcs1 或 pkcs8",
+    ),
+    (
+        "E125",
+        "Private key must include its primes to be exported",
+        "私钥须带有各素因子才能导出",
+    ),
+    ("E126", "Key is not an RSA key", "不是 RSA 密钥"),
+    (
+        "E127",
+        "Error parsing OpenSSH public key",
+        "无法解析 OpenSSH 公钥",
+    ),
+    ("E128", "Error parsing JWK", "无法解析 JWK"),
+    (
+        "E129",
+        "Primes of private key do not multiply to n",
+        "私钥中各素数之积不等于 n",
+    ),
+    ("E130", "p is not prime", "p 不是素数"),
+    ("E131", "q is not prime", "q 不是素数"),
+    (
+        "E132",
+        "A prime factor of private key is not prime",
+        "私钥中有素因子不是素数",
+    ),
+    (
+        "E133",
+        "e * d is not 1 modulo lambda(n)",
+        "e·d 模 λ(n) 不为 1",
+    ),
+    (
+        "E134",
+        "CRT parameters do not match private key",
+        "CRT 参数与私钥不符",
+    ),
+    (
+        "E135",
+        "Key pair failed pairwise consistency test",
+        "密钥对未通过成对一致性自检",
+    ),
+    (
+        "E136",
+        "Unknown signature scheme, expected pkcs1v15 or pss",
+        "未知签名方案，应为 pkcs1v15 或 pss",
+    ),
+    (
+        "E137",
+        "Prime factors of private key are not pairwise coprime",
+        "私钥的各素因子不两两互素",
+    ),
+    ("E105", "Error parsing public key", "无法解析公钥"),
+    ("E106", "Error parsing private key", "无法解析私钥"),
+    ("E107", "Error parsing e", "无法解析 e"),
+    ("E108", "Error parsing n", "无法解析 n"),
+    ("E109", "Error parsing d", "无法解析 d"),
+    (
+        "E111",
+        "n in public key and private key not matching",
+        "公钥与私钥中的 n 不一致",
+    ),
+    (
+        "E112",
+        "You need to regenerate/reset keys",
+        "需要重新生成或重置密钥",
+    ),
+    (
+        "E113",
+        "Invalid input for verify sign",
+        "验证签名的输入格式有误",
+    ),
+    (
+        "E114",
+        "No local TSA key to check timestamp",
+        "没有本地 TSA 密钥，无法检查时间戳",
+    ),
+    (
+        "E138",
+        "Error parsing TSA key file",
+        "无法解析 TSA 密钥文件",
+    ),
+    ("E139", "Error saving TSA key", "无法保存 TSA 密钥"),
+    (
+        "E140",
+        "Number of benchmark runs must be at least 1",
+        "基准测试的次数至少为 1",
+    ),
+    // 数据格式
+    ("E201", "Hex string is empty", "十六进制字符串为空"),
+    (
+        "E202",
+        "Input is longer than 1024 * 64 bits",
+        "输入超过 1024 * 64 位",
+    ),
+    (
+        "E203",
+        "Invalid char in hex string",
+        "十六进制字符串中有非法字符",
+    ),
+    ("E204", "overflow", "溢出"),
+    ("E214", "Division by zero", "除数为 0"),
+    (
+        "E215",
+        "Montgomery modulus must be odd",
+        "蒙哥马利模数必须是奇数",
+    ),
+    (
+        "E216",
+        "Invalid digit for the given radix",
+        "数字不符合所给的进制",
+    ),
+    (
+        "E217",
+        "Radix must be between 2 and 36",
+        "进制必须在 2 到 36 之间",
+    ),
+    ("E218", "Modulus must be greater than 1", "模数必须大于 1"),
+    (
+        "E219",
+        "Jacobi symbol requires an odd positive n",
+        "雅可比符号要求 n 为正奇数",
+    ),
+    ("E220", "Factor does not divide n - 1", "因子不能整除 n - 1"),
+    (
+        "E221",
+        "Factored part of n - 1 must exceed sqrt(n)",
+        "n - 1 中已分解的部分必须大于 sqrt(n)",
+    ),
+    (
+        "E205",
+        "Base64 length is not multiplication of 4",
+        "Base64 长度不是 4 的倍数",
+    ),
+    (
+        "E206",
+        "Invalid padding in base64 string",
+        "Base64 填充有误",
+    ),
+    (
+        "E207",
+        "Invalid char in base64 string",
+        "Base64 中有非法字符",
+    ),
+    (
+        "E208",
+        "Unknown format, expected hex, bin, oct, dec, base36, base64, base64url or raw",
+        "未知格式，应为 hex、bin、oct、dec、base36、base64、base64url 或 raw",
+    ),
+    (
+        "E209",
+        "Raw format can not be used for text output",
+        "raw 格式不能用于文本输出",
+    ),
+    ("E210", "Input is not valid text", "输入不是有效的文本"),
+    (
+        "E211",
+        "Digest length does not match hash algorithm",
+        "摘要长度与哈希算法不符",
+    ),
+    (
+        "E212",
+        "Intended encoded message length too short",
+        "编码后的消息长度过短",
+    ),
+    ("E222", "Decryption error", "解密失败"),
+    ("E223", "Invalid DER encoding", "DER 编码有误"),
+    ("E224", "Invalid JSON", "JSON 格式有误"),
+    ("E225", "Invalid envelope", "数字信封格式有误"),
+    ("E226", "AES-GCM authentication failed", "AES-GCM 认证失败"),
+    (
+        "E227",
+        "Error parsing detached signature",
+        "无法解析分离式签名",
+    ),
+    (
+        "E228",
+        "Unsupported digest algorithm, expected sha256",
+        "不支持的摘要算法，应为 sha256",
+    ),
+    ("E229", "Invalid fingerprint", "指纹格式有误"),
+    (
+        "E230",
+        "Envelope is not addressed to this key",
+        "信封的收件人不是此密钥",
+    ),
+    (
+        "E231",
+        "Envelope needs 1 to 255 recipients",
+        "数字信封须有 1 到 255 个收件人",
+    ),
+    ("E232", "Contact name is empty", "联系人名称为空"),
+    (
+        "E233",
+        "Select at least one contact as recipient",
+        "请至少选择一个联系人作为收件人",
+    ),
+    ("E234", "Error saving contacts", "无法保存通讯录"),
+    (
+        "E213",
+        "Unknown language, expected en or zh",
+        "未知语言，应为 en 或 zh",
+    ),
+    // 随机数
+    (
+        "E301",
+        "Entropy source failed health check: repeated output",
+        "熵源自检失败：输出重复",
+    ),
+    (
+        "E302",
+        "Entropy source failed health check: monobit",
+        "熵源自检失败：单比特频数",
+    ),
+    (
+        "E303",
+        "Entropy source failed health check: long run",
+        "熵源自检失败：长游程",
+    ),
+    // 文本块、时间戳与分解
+    (
+        "E401",
+        "Armored block is truncated (missing checksum or END line)",
+        "文本块不完整（缺少校验行或 END 行）",
+    ),
+    (
+        "E402",
+        "Armor checksum mismatch, the data was corrupted",
+        "文本块校验失败，数据已损坏",
+    ),
+    ("E403", "Unexpected armored block type", "文本块类型不符"),
+    (
+        "E404",
+        "Error parsing timestamp token",
+        "无法解析时间戳令牌",
+    ),
+    ("E405", "Error parsing timestamp", "无法解析时间戳"),
+    ("E406", "Cannot factor 0", "无法分解 0"),
+    ("E407", "Failed to find a factor", "未能找到因子"),
+    ("E408", "n is too large to factor", "n 太大，无法分解"),
+    (
+        "E409",
+        "Wiener's attack failed, d is not small enough",
+        "Wiener 攻击失败，d 不够小",
+    ),
+    (
+        "E410",
+        "e1 and e2 must be coprime for the common modulus attack",
+        "共模攻击要求 e1 与 e2 互素",
+    ),
+    ("E411", "c2 is not invertible modulo n", "c2 模 n 不可逆"),
+    (
+        "E412",
+        "Message is too long for the key",
+        "消息对于该密钥过长",
+    ),
+    (
+        "E413",
+        "Moduli must be pairwise coprime",
+        "各模数必须两两互素",
+    ),
+    (
+        "E414",
+        "Recovered value is not a perfect power, not enough ciphertexts",
+        "还原出的值不是完全幂，密文数量不足",
+    ),
+    (
+        "E415",
+        "Ciphertext is not a perfect cube, the message is too long for this attack",
+        "密文不是完全立方数，消息过长，无法使用此攻击",
+    ),
+    (
+        "E416",
+        "Modulus is too large for baby-step giant-step",
+        "模数过大，无法使用小步大步法",
+    ),
+    ("E417", "g must be invertible modulo p", "g 模 p 必须可逆"),
+    ("E418", "No discrete logarithm exists", "离散对数不存在"),
+    // 命令行
+    ("E501", "parse arg failed", "参数解析失败"),
+    ("E502", "read file failed", "读取文件失败"),
+    ("E503", "write output failed", "写出结果失败"),
+    ("E504", "read seed file failed", "读取种子文件失败"),
+    ("E505", "save settings failed", "保存设置失败"),
+    ("E506", "missing arg", "缺少参数"),
+    ("E507", "read audit log failed", "读取审计日志失败"),
+    ("E508", "write csv failed", "写入 CSV 失败"),
+    ("E509", "write svg failed", "写入 SVG 失败"),
+    ("E510", "implementations diverged", "两种实现的结果不一致"),
+    (
+        "E511",
+        "rsa crate rejected public key",
+        "rsa crate 拒绝了公钥",
+    ),
+    (
+        "E512",
+        "rsa crate rejected private key",
+        "rsa crate 拒绝了私钥",
+    ),
+    (
+        "E513",
+        "rsa crate imported a different modulus",
+        "rsa crate 导入的模数不同",
+    ),
+    ("E514", "rsa_encrypt failed", "rsa_encrypt 失败"),
+    ("E515", "rsa_decrypt failed", "rsa_decrypt 失败"),
+    (
+        "E516",
+        "Seed must be 64 hex digits",
+        "种子须为 64 位十六进制数字",
+    ),
+    (
+        "E517",
+        "--blum and --congruence cannot be used together",
+        "--blum 与 --congruence 不能同时使用",
+    ),
+    (
+        "E518",
+        "--safe-primes and --strong-primes cannot be used together",
+        "--safe-primes 与 --strong-primes 不能同时使用",
+    ),
+    (
+        "E519",
+        "Keys can only be written in hex, bin, oct, dec or base36",
+        "密钥只能以 hex、bin、oct、dec 或 base36 表示",
+    ),
+    (
+        "E520",
+        "Ciphertext and signatures can only be written in hex, base64 or base64url",
+        "密文与签名只能以 hex、base64 或 base64url 表示",
+    ),
+    ("E521", "write png failed", "写入 PNG 失败"),
+    ("E522", "rsa crate failed to encrypt", "rsa crate 加密失败"),
+    ("E523", "rsa crate failed to sign", "rsa crate 签名失败"),
+    // 状态信息
+    ("S001", "Used time: {}us", "用时：{}us"),
+    (
+        "S002",
+        "Estimated generation time: ~{}s",
+        "预计生成时间：约 {}s",
+    ),
+    (
+        "S003",
+        "Estimated generation time: unknown",
+        "预计生成时间：未知",
+    ),
+    (
+        "S004",
+        "n: {} bits, ~{} bits of security ({})",
+        "n：{} 位，约 {} 位安全强度（{}）",
+    ),
+    ("S005", "below 80-bit, insecure", "低于 80 位，不安全"),
+    (
+        "S006",
+        "80-bit, below 112-bit, deprecated",
+        "80 位，低于 112 位，已不推荐",
+    ),
+    ("S007", "112-bit, below 128-bit", "112 位，低于 128 位"),
+    ("S008", "128-bit or above", "128 位及以上"),
+    (
+        "S009",
+        "{} (passphrase-derived demo key, only as strong as the passphrase)",
+        "{}（由口令派生的演示密钥，强度取决于口令）",
+    ),
+    (
+        "S010",
+        "Warning: passphrase-derived keys are for demonstration only",
+        "警告：由口令派生的密钥仅供演示",
+    ),
+    (
+        "S011",
+        "entropy sources for new keys: {}",
+        "生成新密钥时使用的熵源：{}",
+    ),
+    (
+        "S012",
+        "Warning: signature was made by a different key",
+        "警告：签名来自另一把密钥",
+    ),
+    (
+        "S013",
+        "Generating prime {}: {} candidates tested",
+        "正在生成第 {} 个素数：已检测 {} 个候选数",
+    ),
+    (
+        "S014",
+        "Benchmark: {} of {} runs done",
+        "基准测试：已完成 {} / {} 次",
+    ),
+    (
+        "S015",
+        "{}: mean {}us over {} runs",
+        "{}：平均 {}us（共 {} 次）",
+    ),
+];
+
+fn find(msg: &str) -> Option<&'static (&'static str, &'static str, &'static str)> {
+    CATALOG.iter().find(|(_, en, _)| *en == msg)
+}
+
+/// 消息的代码，不在目录中时为 None
+pub fn code(msg: &str) -> Option<&'static str> {
+    find(msg).map(|(code, _, _)| *code)
+}
+
+/// 按当前语言翻译，不在目录中的消息原样返回
+pub fn tr(msg: &str) -> &str {
+    match (find(msg), lang()) {
+        (Some((_, en, _)), Lang::En) => en,
+        (Some((_, _, zh)), Lang::Zh) => zh,
+        (None, _) => msg,
+    }
+}
+
+/// 翻译后依次以 args 填入 `{}`
+pub fn trf(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut res = String::new();
+    let mut args = args.iter();
+    let mut parts = tr(template).split("{}");
+    res.push_str(parts.next().unwrap_or(""));
+    for part in parts {
+        if let Some(arg) = args.next() {
+            res.push_str(&arg.to_string());
+        }
+        res.push_str(part);
+    }
+    res
+}
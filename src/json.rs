@@ -0,0 +1,269 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+const INVALID: &str = "Invalid JSON";
+/// 对象与数组最多嵌套的层数。JWK 只需 3 层（私钥的 `oth` 中的对象），
+/// 限制层数使恶意构造的深层嵌套不会耗尽递归下降解析的栈空间
+const MAX_DEPTH: usize = 32;
+
+/// JSON 值（RFC 8259），对象保留键的顺序，数字保留原文
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// 对象中键为 key 的值
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// 紧凑的单行表示
+    pub fn to_json(&self) -> String {
+        match self {
+            Value::Null => "null".to_owned(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.clone(),
+            Value::String(s) => quote(s),
+            Value::Array(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_json).collect();
+                format!("[{}]", items.join(","))
+            }
+            Value::Object(members) => {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|(k, v)| format!("{}:{}", quote(k), v.to_json()))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut res = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// 解析恰好一个 JSON 值，前后可以有空白
+pub fn parse(text: &str) -> Result<Value, &'static str> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some(_) => Err(INVALID),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// 当前所在的对象与数组的层数
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), &'static str> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(x) if x == c => Ok(()),
+            _ => Err(INVALID),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, &'static str> {
+        for c in word.chars() {
+            if self.chars.next() != Some(c) {
+                return Err(INVALID);
+            }
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, &'static str> {
+        self.skip_whitespace();
+        match self.chars.peek().ok_or(INVALID)? {
+            '{' | '[' => {
+                if self.depth == MAX_DEPTH {
+                    return Err(INVALID);
+                }
+                self.depth += 1;
+                let res = if self.chars.peek() == Some(&'{') {
+                    self.object()
+                } else {
+                    self.array()
+                };
+                self.depth -= 1;
+                res
+            }
+            '"' => Ok(Value::String(self.string()?)),
+            't' => self.literal("true", Value::Bool(true)),
+            'f' => self.literal("false", Value::Bool(false)),
+            'n' => self.literal("null", Value::Null),
+            '-' | '0'..='9' => Ok(Value::Number(self.number()?)),
+            _ => Err(INVALID),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, &'static str> {
+        self.expect('{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(members)),
+                _ => return Err(INVALID),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, &'static str> {
+        self.expect('[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(INVALID),
+            }
+        }
+    }
+
+    /// 字符串，\u 转义只支持基本多文种平面内的字符
+    fn string(&mut self) -> Result<String, &'static str> {
+        if self.chars.next() != Some('"') {
+            return Err(INVALID);
+        }
+        let mut res = String::new();
+        loop {
+            match self.chars.next().ok_or(INVALID)? {
+                '"' => return Ok(res),
+                '\\' => res.push(match self.chars.next().ok_or(INVALID)? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .filter(|_| hex.len() == 4)
+                            .and_then(char::from_u32)
+                            .ok_or(INVALID)?
+                    }
+                    _ => return Err(INVALID),
+                }),
+                c if (c as u32) < 0x20 => return Err(INVALID),
+                c => res.push(c),
+            }
+        }
+    }
+
+    /// 数字只检查由合法的字符组成，保留原文
+    fn number(&mut self) -> Result<String, &'static str> {
+        let mut res = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+        {
+            res.push(c);
+        }
+        match res.parse::<f64>() {
+            Ok(_) => Ok(res),
+            Err(_) => Err(INVALID),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trip() {
+        let text = r#"{"kty":"RSA","n":"AQAB","oth":[{"r":"Aw"}],"ok":true,"x":null,"v":-1.5e3}"#;
+        let value = parse(text).unwrap();
+        assert_eq!(value.get("kty").and_then(Value::as_str), Some("RSA"));
+        assert_eq!(
+            value.get("oth").and_then(Value::as_array).map(|a| a.len()),
+            Some(1)
+        );
+        assert_eq!(parse(&value.to_json()).unwrap(), value);
+        for bad in ["", "{", r#"{"a":}"#, "[1,]", "tru", r#"{"a":1} x"#] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    /// 嵌套超过 MAX_DEPTH 层时报错，而不是递归到栈溢出
+    #[test]
+    fn nesting_depth_limit() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(parse(&nested(MAX_DEPTH + 1)), Err(INVALID));
+        assert_eq!(parse(&nested(1_000_000)), Err(INVALID));
+    }
+}
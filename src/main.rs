@@ -3,48 +3,1047 @@
 use iced::{Application, Error, Settings};
 use random_string;
 
+mod aes;
 mod algorithms;
 mod app;
+mod armor;
+mod asn1;
+mod attacks;
+mod audit;
+mod bench;
 mod bigint;
+#[cfg(feature = "num-bigint")]
+mod bignum;
+mod contacts;
+#[cfg(feature = "differential")]
+mod differential;
+mod encoding;
+mod entropy;
+mod envelope;
+mod factor;
+mod hash;
+mod i18n;
+mod json;
+mod modint;
+mod padding;
+mod png;
 mod rsa;
+mod settings;
+mod stream;
+mod trace;
+mod tsa;
 mod utils;
 
-#[derive(Debug)]
+use bench::BenchResult;
+use bigint::BigInt;
+use encoding::Format;
+use std::fmt;
+use std::io::Write;
+use trace::Trace;
+
 enum AppError {
     IcedError(Error),
     OtherError(&'static str),
 }
 
+/// main 返回错误时以 Debug 打印，这里给出带代码的本地化信息
+impl fmt::Debug for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::IcedError(e) => write!(f, "{:?}", e),
+            AppError::OtherError(e) => match i18n::code(e) {
+                Some(code) => write!(f, "[{}] {}", code, i18n::tr(e)),
+                None => write!(f, "{}", e),
+            },
+        }
+    }
+}
+
+fn parse_usize(s: &str) -> Result<usize, AppError> {
+    s.parse()
+        .map_err(|_| AppError::OtherError("parse arg failed"))
+}
+
+/// 按 --format 解析一个数，以 @ 开头时从文件读取（raw 格式只能这样输入）
+fn parse_number(format: Format, s: &str) -> Result<BigInt, AppError> {
+    let data = match s.strip_prefix('@') {
+        Some(path) => std::fs::read(path).map_err(|_| AppError::OtherError("read file failed"))?,
+        None => s.as_bytes().to_vec(),
+    };
+    let bytes = format.decode(&data).map_err(|e| AppError::OtherError(e))?;
+    Ok(BigInt::from_bytes_be(&bytes))
+}
+
+/// 按 --format 输出一个数，raw 格式直接写出二进制
+fn write_number(format: Format, n: &BigInt) -> Result<(), AppError> {
+    let mut out = std::io::stdout();
+    out.write_all(&format.encode(&n.to_bytes_be()))
+        .and_then(|_| match format {
+            Format::Raw => Ok(()),
+            _ => out.write_all(b"\n"),
+        })
+        .map_err(|_| AppError::OtherError("write output failed"))
+}
+
+/// 密文块与签名按 --format 编码，只支持十六进制、base64 与 base64url
+fn block_encoding(format: Format) -> Result<rsa::BlockEncoding, AppError> {
+    match format {
+        Format::Hex => Ok(rsa::BlockEncoding::Hex),
+        Format::Base64 => Ok(rsa::BlockEncoding::Base64),
+        Format::Base64Url => Ok(rsa::BlockEncoding::Base64Url),
+        _ => Err(AppError::OtherError(
+            "Ciphertext and signatures can only be written in hex, base64 or base64url",
+        )),
+    }
+}
+
+/// 按 --format 把一个数写成可与其他文字混排的文本，十六进制与 `fmt_hex` 相同
+fn fmt_number(format: Format, n: &BigInt) -> Result<String, AppError> {
+    match format {
+        Format::Hex => Ok(n.fmt_hex()),
+        Format::Radix(r) => Ok(n.to_str_radix(r)),
+        _ => format
+            .encode_text(&n.to_bytes_be())
+            .map_err(|e| AppError::OtherError(e)),
+    }
+}
+
+/// "n,e" 与 "n,d,p,q,..." 形式的密钥按 --format 的进制读写，不支持 base64 等字节编码
+fn key_radix(format: Format) -> Result<u32, AppError> {
+    match format {
+        Format::Hex => Ok(16),
+        Format::Radix(r) => Ok(r),
+        _ => Err(AppError::OtherError(
+            "Keys can only be written in hex, bin, oct, dec or base36",
+        )),
+    }
+}
+
+/// 以 --armor 输出的密钥文本块的类型
+const PUB_KEY_ARMOR: &str = "THSS RSA PUBLIC KEY";
+const PRIV_KEY_ARMOR: &str = "THSS RSA PRIVATE KEY";
+
+/// --armor 时把密钥包装为带 CRC24 校验行的文本块
+fn armor_key(armored: bool, kind: &str, key: String) -> String {
+    if armored {
+        armor::armor(kind, key.as_bytes())
+    } else {
+        key
+    }
+}
+
+/// 密钥参数既可以是 "n,e" 等文本，也可以是 --armor 输出的文本块
+fn dearmor_key(kind: &str, key: &str) -> Result<String, AppError> {
+    if armor::is_armored(key) {
+        armor::dearmor_text(kind, key).map_err(|e| AppError::OtherError(e.message()))
+    } else {
+        Ok(key.to_owned())
+    }
+}
+
+fn parse_keys(
+    format: Format,
+    pub_key: &str,
+    priv_key: &str,
+) -> Result<(rsa::PublicKey, rsa::PrivateKey), AppError> {
+    let pub_key = dearmor_key(PUB_KEY_ARMOR, pub_key)?;
+    let priv_key = dearmor_key(PRIV_KEY_ARMOR, priv_key)?;
+    match key_radix(format)? {
+        16 => rsa::key_from_str(&pub_key, &priv_key),
+        r => rsa::key_from_str_radix(&pub_key, &priv_key, r),
+    }
+    .map_err(|e| AppError::OtherError(e))
+}
+
+/// 解析公钥，e 可以是任意大的数
+fn parse_pub_key_big_e(format: Format, pub_key: &str) -> Result<(BigInt, BigInt), AppError> {
+    let pub_key = dearmor_key(PUB_KEY_ARMOR, pub_key)?;
+    match key_radix(format)? {
+        16 => rsa::pub_key_from_str_big_e(&pub_key),
+        r => rsa::pub_key_from_str_radix(&pub_key, r),
+    }
+    .map_err(|e| AppError::OtherError(e))
+}
+
+fn parse_pub_key(format: Format, pub_key: &str) -> Result<rsa::PublicKey, AppError> {
+    let (n, e) = parse_pub_key_big_e(format, pub_key)?;
+    let e = u64::try_from(&e).map_err(|_| AppError::OtherError("Error parsing e"))?;
+    rsa::PublicKey::new(&n, e).map_err(|e| AppError::OtherError(e))
+}
+
+/// "n,e" 或 "n,d"
+fn fmt_pair(format: Format, n: &BigInt, x: &BigInt) -> Result<String, AppError> {
+    Ok(match key_radix(format)? {
+        16 => format!("{},{:08x}", n.fmt_hex(), x),
+        r => format!("{},{}", n.to_str_radix(r), x.to_str_radix(r)),
+    })
+}
+
+fn fmt_keys(
+    format: Format,
+    armored: bool,
+    pub_key: &rsa::PublicKey,
+    priv_key: &rsa::PrivateKey,
+) -> Result<String, AppError> {
+    let (pub_str, priv_str) = match key_radix(format)? {
+        16 => rsa::fmt_key(pub_key, priv_key),
+        r => rsa::fmt_key_radix(pub_key, priv_key, r),
+    };
+    Ok(format!(
+        "{}\n{}",
+        armor_key(armored, PUB_KEY_ARMOR, pub_str),
+        armor_key(armored, PRIV_KEY_ARMOR, priv_str)
+    ))
+}
+
+fn audit_status<T>(res: &Result<T, &'static str>) -> &'static str {
+    match res {
+        Ok(_) => "ok",
+        Err(_) => "failed",
+    }
+}
+
+/// 取出形如 `--name value` 的参数，并将其从 args 中移除
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let idx = args.iter().position(|a| a == name)?;
+    if idx + 1 >= args.len() {
+        args.remove(idx);
+        return None;
+    }
+    let value = args.remove(idx + 1);
+    args.remove(idx);
+    Some(value)
+}
+
+/// 取出可重复给出的选项的所有值
+fn take_options(args: &mut Vec<String>, name: &str) -> Vec<String> {
+    std::iter::from_fn(|| take_option(args, name)).collect()
+}
+
 fn main() -> Result<(), AppError> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    // --trace 可出现在任意位置：只执行一次，并以表格形式打印中间步骤
+    let trace_mode = args.iter().any(|a| a == "--trace");
+    args.retain(|a| a != "--trace");
+    // 计时结果导出为 CSV 原始数据或 SVG、PNG 柱状图
+    let csv_path = take_option(&mut args, "--csv");
+    let svg_path = take_option(&mut args, "--svg");
+    let png_path = take_option(&mut args, "--png");
+    // 所有子命令输入输出数据时使用的编码
+    let format = match take_option(&mut args, "--format") {
+        Some(name) => Format::from_name(&name).map_err(|e| AppError::OtherError(e))?,
+        None => Format::Hex,
+    };
+    // 提示与错误信息的语言
+    let lang = match take_option(&mut args, "--lang") {
+        Some(name) => i18n::Lang::from_name(&name).map_err(|e| AppError::OtherError(e))?,
+        None => i18n::Lang::from_env(),
+    };
+    i18n::set_lang(lang);
+    // 将每次密码学操作追加到审计日志中
+    if args.iter().any(|a| a == "--audit") {
+        audit::set_enabled(true);
+        args.retain(|a| a != "--audit");
+    }
+    // 混入 CPU 硬件随机数，也可在设置中开启
+    let hw_rng = args.iter().any(|a| a == "--hw-rng");
+    args.retain(|a| a != "--hw-rng");
+    entropy::set_hardware(hw_rng || settings::Settings::load().hardware_rng);
+    // 生成素数与 isprime 的 Miller-Rabin 轮数，也可在设置中指定
+    let mr_rounds = match take_option(&mut args, "--mr-rounds") {
+        Some(r) => {
+            Some(rsa::check_mr_rounds(parse_usize(&r)?).map_err(|e| AppError::OtherError(e))?)
+        }
+        None => settings::Settings::load().mr_rounds,
+    };
+    let mut keygen = rsa::KeygenParams {
+        mr_rounds,
+        ..rsa::KeygenParams::default()
+    };
+    // genkey 要求 p, q ≡ r (mod m)，--blum 即 p, q ≡ 3 (mod 4)，n 为 Blum 整数
+    let congruence = match take_option(&mut args, "--congruence") {
+        Some(c) => {
+            let (r, m) = c.split_once('/').ok_or(AppError::OtherError(
+                "Congruence must be given as residue/modulus",
+            ))?;
+            let (r, m) = (parse_usize(r)? as u64, parse_usize(m)? as u64);
+            Some(rsa::check_congruence(r, m).map_err(|e| AppError::OtherError(e))?)
+        }
+        None => None,
+    };
+    if args.iter().any(|a| a == "--blum") {
+        if congruence.is_some() {
+            return Err(AppError::OtherError(
+                "--blum and --congruence cannot be used together",
+            ));
+        }
+        keygen.congruence = Some((3, 4));
+        args.retain(|a| a != "--blum");
+    } else {
+        keygen.congruence = congruence;
+    }
+    // genkey 以多个线程并行检测素数候选
+    if let Some(t) = take_option(&mut args, "--threads") {
+        keygen.threads =
+            Some(rsa::check_prime_threads(parse_usize(&t)?).map_err(|e| AppError::OtherError(e))?);
+    }
+    // genkey 以 k 个素数之积作为 n
+    if let Some(k) = take_option(&mut args, "--primes") {
+        keygen.prime_count =
+            rsa::check_prime_count(parse_usize(&k)?).map_err(|e| AppError::OtherError(e))?;
+    }
+    // genkey 以安全素数或 X9.31 强素数作为 p, q
+    if args.iter().any(|a| a == "--safe-primes") {
+        keygen.kind = rsa::PrimeKind::Safe;
+        args.retain(|a| a != "--safe-primes");
+    }
+    if args.iter().any(|a| a == "--strong-primes") {
+        if keygen.kind == rsa::PrimeKind::Safe {
+            return Err(AppError::OtherError(
+                "--safe-primes and --strong-primes cannot be used together",
+            ));
+        }
+        keygen.kind = rsa::PrimeKind::Strong;
+        args.retain(|a| a != "--strong-primes");
+    }
+    // genkey 故意生成带有弱点的密钥，用于演示攻击
+    let weakness = match take_option(&mut args, "--weak") {
+        Some(name) => Some(rsa::Weakness::from_name(&name).map_err(|e| AppError::OtherError(e))?),
+        None => None,
+    };
+    // genkey 由口令确定性地派生密钥
+    let passphrase = take_option(&mut args, "--from-passphrase");
+    // genkey 以 32 字节的种子驱动 ChaCha20，确定性地生成密钥
+    let keygen_seed = match take_option(&mut args, "--seed") {
+        Some(hex) => Some(
+            <[u8; 32]>::try_from(encoding::hex_decode(&hex).unwrap_or_default())
+                .map_err(|_| AppError::OtherError("Seed must be 64 hex digits"))?,
+        ),
+        None => None,
+    };
+    // genkey 与 importkey 以带 CRC24 校验行的文本块输出密钥
+    let armored = args.iter().any(|a| a == "--armor");
+    args.retain(|a| a != "--armor");
+    // seal 的收件人公钥，可重复给出
+    let recipients = take_options(&mut args, "--to");
+    // 将种子文件的内容混入随机数源
+    if let Some(path) = take_option(&mut args, "--seed-file") {
+        let seed =
+            std::fs::read(path).map_err(|_| AppError::OtherError("read seed file failed"))?;
+        entropy::add_entropy(&seed);
+    }
+
     if args.is_empty() || args.len() == 1 {
         return app::App::run(Settings::default()).map_err(|e| AppError::IcedError(e));
     } else if args.len() >= 3 {
-        let keylen: usize = args[2]
-            .parse()
-            .map_err(|_| AppError::OtherError("parse arg failed"))?;
+        let mut trace = Trace::new();
+        let mut bench = BenchResult::new(args[1..].join(" "));
         match args[1].as_str() {
             "genkey" => {
-                for _ in 0..10 {
-                    let (t, _) = utils::count_time(|| rsa::gen_keys(keylen));
-                    println!("{}", t)
+                let keylen = parse_usize(&args[2])?;
+                if let Some(weakness) = weakness {
+                    let (t, res) =
+                        utils::count_time(|| rsa::gen_weak_keys(keylen, weakness, &keygen));
+                    audit::log(
+                        "genkey",
+                        res.as_ref().ok().map(|(n, _, _)| n),
+                        args[2].as_bytes(),
+                        audit_status(&res),
+                        t,
+                    );
+                    let (n, e, d) = res.map_err(|e| AppError::OtherError(e))?;
+                    println!(
+                        "{}\n{}",
+                        armor_key(armored, PUB_KEY_ARMOR, fmt_pair(format, &n, &e)?),
+                        armor_key(armored, PRIV_KEY_ARMOR, fmt_pair(format, &n, &d)?)
+                    );
+                } else if let Some(passphrase) = &passphrase {
+                    eprintln!(
+                        "{}",
+                        i18n::tr("Warning: passphrase-derived keys are for demonstration only")
+                    );
+                    let (t, res) =
+                        utils::count_time(|| rsa::gen_keys_from_passphrase(keylen, passphrase));
+                    // 不记录口令的摘要，它足以离线验证猜测的口令
+                    audit::log(
+                        "genkey",
+                        res.as_ref().ok().map(|(pub_key, _)| pub_key.n()),
+                        args[2].as_bytes(),
+                        audit_status(&res),
+                        t,
+                    );
+                    let (pub_key, priv_key) = res.map_err(|e| AppError::OtherError(e))?;
+                    println!("{}", fmt_keys(format, armored, &pub_key, &priv_key)?);
+                } else if let Some(seed) = keygen_seed {
+                    let (t, res) = utils::count_time(|| rsa::gen_keys_seeded(keylen, seed));
+                    audit::log(
+                        "genkey",
+                        res.as_ref().ok().map(|(pub_key, _)| pub_key.n()),
+                        args[2].as_bytes(),
+                        audit_status(&res),
+                        t,
+                    );
+                    let (pub_key, priv_key) = res.map_err(|e| AppError::OtherError(e))?;
+                    println!("{}", fmt_keys(format, armored, &pub_key, &priv_key)?);
+                } else if trace_mode {
+                    let (t, res) = utils::count_time(|| {
+                        rsa::gen_keys_traced(keylen, &keygen, Some(&mut trace))
+                    });
+                    audit::log(
+                        "genkey",
+                        res.as_ref().ok().map(|(pub_key, _)| pub_key.n()),
+                        args[2].as_bytes(),
+                        audit_status(&res),
+                        t,
+                    );
+                    res.map_err(|e| AppError::OtherError(e))?;
+                } else if keylen >= 4096 {
+                    // 大密钥生成耗时较长，在 stderr 上持续显示进度
+                    let (t, res) = utils::count_time(|| {
+                        rsa::gen_keys_with_progress(
+                            keylen,
+                            &keygen,
+                            None,
+                            Some(&mut |idx, tried| {
+                                eprint!("\rprime {}: {} candidates tested", idx + 1, tried)
+                            }),
+                        )
+                    });
+                    eprintln!();
+                    audit::log(
+                        "genkey",
+                        res.as_ref().ok().map(|(pub_key, _)| pub_key.n()),
+                        args[2].as_bytes(),
+                        audit_status(&res),
+                        t,
+                    );
+                    res.map_err(|e| AppError::OtherError(e))?;
+                    println!("{}", t);
+                    bench.times.push(t);
+                } else {
+                    for _ in 0..10 {
+                        let (t, res) =
+                            utils::count_time(|| rsa::gen_keys_with_params(keylen, &keygen));
+                        audit::log(
+                            "genkey",
+                            res.as_ref().ok().map(|(pub_key, _)| pub_key.n()),
+                            args[2].as_bytes(),
+                            audit_status(&res),
+                            t,
+                        );
+                        res.map_err(|e| AppError::OtherError(e))?;
+                        println!("{}", t);
+                        bench.times.push(t);
+                    }
+                }
+                // 计时结果供 GUI 估计生成时间
+                if !bench.times.is_empty() {
+                    let mut settings = settings::Settings::load();
+                    for t in &bench.times {
+                        settings.record_keygen(keylen, *t);
+                    }
+                    settings
+                        .save()
+                        .map_err(|_| AppError::OtherError("save settings failed"))?;
                 }
             }
             "encrypt" => {
-                let (n, _) = rsa::gen_keys(keylen);
-                let m = n.barrett_m();
-                let msglen: usize = args[3]
-                    .parse()
-                    .map_err(|_| AppError::OtherError("parse arg failed"))?;
-                for _ in 0..10 {
+                let keylen = parse_usize(&args[2])?;
+                let (pub_key, _) = rsa::gen_keys_with_params(keylen, &keygen)
+                    .map_err(|e| AppError::OtherError(e))?;
+                let msglen = parse_usize(args.get(3).ok_or(AppError::OtherError("missing arg"))?)?;
+                let encoding = block_encoding(format)?;
+                if trace_mode {
                     let msg =
                         random_string::generate(msglen, random_string::charsets::ALPHANUMERIC);
-                    let (t, _) = utils::count_time(|| rsa::encrypt(&msg, &n, &m));
-                    println!("{}", t)
+                    let (t, _) = utils::count_time(|| {
+                        rsa::encrypt_traced(&msg, &pub_key, encoding, Some(&mut trace))
+                    });
+                    audit::log("encrypt", Some(pub_key.n()), msg.as_bytes(), "ok", t);
+                } else {
+                    for _ in 0..10 {
+                        let msg =
+                            random_string::generate(msglen, random_string::charsets::ALPHANUMERIC);
+                        let (t, _) = utils::count_time(|| {
+                            rsa::encrypt_traced(&msg, &pub_key, encoding, None)
+                        });
+                        audit::log("encrypt", Some(pub_key.n()), msg.as_bytes(), "ok", t);
+                        println!("{}", t);
+                        bench.times.push(t);
+                    }
+                }
+            }
+            // modpow <a> <b> <n>
+            "modpow" if args.len() >= 5 => {
+                let a = parse_number(format, &args[2])?;
+                let b = parse_number(format, &args[3])?;
+                let n = parse_number(format, &args[4])?;
+                let res = if trace_mode {
+                    algorithms::BarrettContext::new(&n).mod_pow_traced(&a, &b, Some(&mut trace))
+                } else {
+                    a.mod_pow(&b, &n)
+                };
+                write_number(format, &res)?;
+            }
+            // jacobi <a> <n>，n 为正奇数
+            "jacobi" if args.len() >= 4 => {
+                let a = parse_number(format, &args[2])?;
+                let n = parse_number(format, &args[3])?;
+                if !n.bit(0) {
+                    return Err(AppError::OtherError(
+                        "Jacobi symbol requires an odd positive n",
+                    ));
+                }
+                println!("{}", algorithms::jacobi(&a, &n));
+            }
+            // dlog <g> <h> <p>，小步大步法求 g^x = h mod p，p 不超过 40 位
+            "dlog" if args.len() >= 5 => {
+                let g = parse_number(format, &args[2])?;
+                let h = parse_number(format, &args[3])?;
+                let p = parse_number(format, &args[4])?;
+                let (t, x) = utils::count_time(|| algorithms::bsgs(&g, &h, &p));
+                let x = x
+                    .map_err(|e| AppError::OtherError(e))?
+                    .ok_or(AppError::OtherError("No discrete logarithm exists"))?;
+                write_number(format, &x)?;
+                println!("{}", i18n::trf("Used time: {}us", &[&t]));
+            }
+            // pocklington <n> <q1,q2,...>，q 为 n - 1 的素因子，输出各 q 的见证数
+            "pocklington" if args.len() >= 4 => {
+                let n = parse_number(format, &args[2])?;
+                let factors = args[3]
+                    .split(',')
+                    .map(|q| parse_number(format, q))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match algorithms::pocklington(&n, &factors).map_err(|e| AppError::OtherError(e))? {
+                    Some(witnesses) => {
+                        for (q, a) in factors.iter().zip(witnesses) {
+                            println!("{}: {}", fmt_number(format, q)?, a);
+                        }
+                        println!("true");
+                    }
+                    None => println!("false"),
+                }
+            }
+            // isprime <n>
+            "isprime" => {
+                let n = parse_number(format, &args[2])?;
+                println!(
+                    "{}",
+                    algorithms::miller_rabin_traced(
+                        &n,
+                        mr_rounds.unwrap_or(algorithms::MR_ROUNDS),
+                        trace_mode.then_some(&mut trace)
+                    )
+                );
+            }
+            // factor <n>，至多 factor::MAX_BITS 位
+            "factor" => {
+                let n = parse_number(format, &args[2])?;
+                if n.bit_length() > factor::MAX_BITS {
+                    return Err(AppError::OtherError("n is too large to factor"));
+                }
+                let n = u128::try_from(&n).unwrap();
+                let (t, res) = utils::count_time(|| factor::factorize(n));
+                for (p, method) in res.map_err(|e| AppError::OtherError(e))? {
+                    println!("{} ({})", fmt_number(format, &BigInt::from(p))?, method);
+                }
+                println!("{}", i18n::trf("Used time: {}us", &[&t]));
+            }
+            // crack <pubkey> [max_iter]，以费马分解或 Pollard rho 分解模数并恢复私钥
+            "crack" => {
+                let pub_key = parse_pub_key(format, &args[2])?;
+                let (n, e) = (pub_key.n().clone(), pub_key.e());
+                let max_iter = match args.get(3) {
+                    Some(s) => parse_usize(s)? as u64,
+                    None => rsa::CRACK_MAX_ITER,
+                };
+                let (t, res) = utils::count_time(|| rsa::crack(&n, e, max_iter));
+                let (p, q, d, method) = res.map_err(|e| AppError::OtherError(e))?;
+                println!(
+                    "p: {}\nq: {} ({})",
+                    fmt_number(format, &p)?,
+                    fmt_number(format, &q)?,
+                    method
+                );
+                println!("{}", fmt_pair(format, &n, &d)?);
+                println!("{}", i18n::trf("Used time: {}us", &[&t]));
+            }
+            // wiener <pubkey>，e 可以很大；d 足够小时恢复 d
+            "wiener" => {
+                let (n, e) = parse_pub_key_big_e(format, &args[2])?;
+                let (t, d) = utils::count_time(|| attacks::wiener_attack(&n, &e));
+                let d = d.ok_or(AppError::OtherError(
+                    "Wiener's attack failed, d is not small enough",
+                ))?;
+                println!("{}", fmt_pair(format, &n, &d)?);
+                println!("{}", i18n::trf("Used time: {}us", &[&t]));
+            }
+            // commonmod <keylen> <message>，以同一 n、两个互素的 e 加密同一消息，再由共模攻击解出
+            "commonmod" if args.len() >= 4 => {
+                let keylen = parse_usize(&args[2])?;
+                let (pub_key, _) = rsa::gen_keys_with_params(keylen, &keygen)
+                    .map_err(|e| AppError::OtherError(e))?;
+                let n = pub_key.n().clone();
+                let m = BigInt::from_bytes_be(args[3].as_bytes());
+                if m >= n {
+                    return Err(AppError::OtherError("Message is too long for the key"));
+                }
+                let (e1, e2) = (BigInt::from(rsa::E), BigInt::from(65537u64));
+                let (c1, c2) = (m.mod_pow(&e1, &n), m.mod_pow(&e2, &n));
+                println!("n: {}", fmt_number(format, &n)?);
+                let (se1, sc1) = (fmt_number(format, &e1)?, fmt_number(format, &c1)?);
+                let (se2, sc2) = (fmt_number(format, &e2)?, fmt_number(format, &c2)?);
+                println!("e1: {}, c1: {}", se1, sc1);
+                println!("e2: {}, c2: {}", se2, sc2);
+                let recovered = attacks::common_modulus(&c1, &c2, &e1, &e2, &n)
+                    .map_err(|e| AppError::OtherError(e))?;
+                println!("{}", String::from_utf8_lossy(&recovered.to_bytes_be()));
+                println!("{}", recovered == m);
+            }
+            // broadcast <keylen> <message>，以 e = 3 把同一消息发给三个接收者，再由广播攻击解出。
+            // 只演示加密一侧，模数借用 gen_keys 生成的密钥
+            "broadcast" if args.len() >= 4 => {
+                let keylen = parse_usize(&args[2])?;
+                let m = BigInt::from_bytes_be(args[3].as_bytes());
+                let e = BigInt::from(3u64);
+                let mut ciphertexts = vec![];
+                for _ in 0..3 {
+                    let (pub_key, _) = rsa::gen_keys_with_params(keylen, &keygen)
+                        .map_err(|e| AppError::OtherError(e))?;
+                    let n = pub_key.n().clone();
+                    if m >= n {
+                        return Err(AppError::OtherError("Message is too long for the key"));
+                    }
+                    let c = m.mod_pow(&e, &n);
+                    println!(
+                        "n: {}, c: {}",
+                        fmt_number(format, &n)?,
+                        fmt_number(format, &c)?
+                    );
+                    ciphertexts.push((c, n));
+                }
+                let recovered =
+                    attacks::hastad_broadcast(&ciphertexts).map_err(|e| AppError::OtherError(e))?;
+                println!("{}", String::from_utf8_lossy(&recovered.to_bytes_be()));
+                println!("{}", recovered == m);
+            }
+            // cuberoot <keylen> <message>，以 e = 3 加密短消息，m^3 < n 时直接开立方即可解出
+            "cuberoot" if args.len() >= 4 => {
+                let keylen = parse_usize(&args[2])?;
+                let (pub_key, _) = rsa::gen_keys_with_params(keylen, &keygen)
+                    .map_err(|e| AppError::OtherError(e))?;
+                let n = pub_key.n().clone();
+                let m = BigInt::from_bytes_be(args[3].as_bytes());
+                if m >= n {
+                    return Err(AppError::OtherError("Message is too long for the key"));
+                }
+                let c = m.mod_pow(&BigInt::from(3u64), &n);
+                println!("n: {}", fmt_number(format, &n)?);
+                println!("c: {}", fmt_number(format, &c)?);
+                let recovered = attacks::low_exponent(&c, 3).ok_or(AppError::OtherError(
+                    "Ciphertext is not a perfect cube, the message is too long for this attack",
+                ))?;
+                println!("{}", String::from_utf8_lossy(&recovered.to_bytes_be()));
+                println!("{}", recovered == m);
+            }
+            // oaep <keylen> <message> [label]，以 RSAES-OAEP 加密后再解密
+            "oaep" if args.len() >= 4 => {
+                let keylen = parse_usize(&args[2])?;
+                let (pub_key, priv_key) = rsa::gen_keys_with_params(keylen, &keygen)
+                    .map_err(|e| AppError::OtherError(e))?;
+                let label = args.get(4).map(|l| l.as_bytes()).unwrap_or(&[]);
+                let c = rsa::encrypt_oaep(args[3].as_bytes(), &pub_key, label)
+                    .map_err(|e| AppError::OtherError(e))?;
+                println!("c: {}", fmt_number(format, &BigInt::from_bytes_be(&c))?);
+                let m =
+                    rsa::decrypt_oaep(&c, &priv_key, label).map_err(|e| AppError::OtherError(e))?;
+                println!("{}", String::from_utf8_lossy(&m));
+                println!("{}", m == args[3].as_bytes());
+            }
+            // pss <keylen> <message> [saltlen]，以 RSASSA-PSS 签名后再验证
+            "pss" if args.len() >= 4 => {
+                let keylen = parse_usize(&args[2])?;
+                let salt_len = match args.get(4) {
+                    Some(l) => parse_usize(l)?,
+                    None => rsa::PSS_SALT_LEN,
+                };
+                let (pub_key, priv_key) = rsa::gen_keys_with_params(keylen, &keygen)
+                    .map_err(|e| AppError::OtherError(e))?;
+                let s = rsa::sign_pss(args[3].as_bytes(), &priv_key, salt_len)
+                    .map_err(|e| AppError::OtherError(e))?;
+                println!("s: {}", fmt_number(format, &BigInt::from_bytes_be(&s))?);
+                println!(
+                    "{}",
+                    rsa::ver_sign_pss(args[3].as_bytes(), &s, &pub_key, salt_len)
+                );
+            }
+            // gcdbench <bits> [rounds]，比较三种最大公约数算法在随机数上的平均用时
+            "gcdbench" => {
+                let bits = parse_usize(&args[2])?;
+                let rounds = match args.get(3) {
+                    Some(s) => parse_usize(s)?,
+                    None => 100,
+                };
+                let pairs: Vec<(BigInt, BigInt)> = (0..rounds)
+                    .map(|_| (BigInt::rand_bits(bits), BigInt::rand_bits(bits)))
+                    .collect();
+                let algos = [
+                    (
+                        "euclid",
+                        algorithms::euclid_gcd as fn(&BigInt, &BigInt) -> BigInt,
+                    ),
+                    ("lehmer", algorithms::lehmer_gcd),
+                    ("binary", algorithms::binary_gcd),
+                ];
+                for (name, gcd) in algos {
+                    let (t, _) = utils::count_time(|| {
+                        pairs.iter().map(|(a, b)| gcd(a, b)).collect::<Vec<_>>()
+                    });
+                    println!("{}: {}us", name, t / rounds.max(1) as u128);
                 }
             }
+            // keyinfo <pubkey>，即 GUI 中显示的 "n,e" 格式
+            "keyinfo" => {
+                let pub_key = parse_pub_key(format, &args[2])?;
+                println!("e: {}", fmt_number(format, &BigInt::from(pub_key.e()))?);
+                println!("{}", rsa::fmt_strength(pub_key.n()));
+                let fingerprint = pub_key.fingerprint();
+                println!("{}", fingerprint);
+                let digest = match format {
+                    Format::Hex => fingerprint.to_hex(),
+                    _ => fmt_number(format, &BigInt::from_bytes_be(&fingerprint.0))?,
+                };
+                println!("{}", digest);
+                println!(
+                    "{}",
+                    i18n::trf(
+                        "entropy sources for new keys: {}",
+                        &[&entropy::sources().join(", ")]
+                    )
+                );
+            }
+            // encryptfile <pubkey> <in> <out>，逐块加密任意文件，不把整个文件读入内存
+            "encryptfile" if args.len() >= 5 => {
+                let pub_key = parse_pub_key(format, &args[2])?;
+                let input = std::fs::File::open(&args[3])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                let output = std::fs::File::create(&args[4])
+                    .map_err(|_| AppError::OtherError("write output failed"))?;
+                let mut writer =
+                    stream::RsaEncryptWriter::new(std::io::BufWriter::new(output), &pub_key);
+                let (t, res) = utils::count_time(|| {
+                    std::io::copy(&mut std::io::BufReader::new(input), &mut writer)
+                        .and_then(|_| writer.finish())
+                });
+                // 文件不整个读入内存，记录的是文件名的摘要
+                let status = if res.is_ok() { "ok" } else { "failed" };
+                audit::log("encrypt", Some(pub_key.n()), args[3].as_bytes(), status, t);
+                res.map_err(|_| AppError::OtherError("write output failed"))?;
+                println!("{}", t);
+            }
+            // decryptfile <pubkey> <privkey> <in> <out>
+            "decryptfile" if args.len() >= 6 => {
+                let (_, priv_key) = parse_keys(format, &args[2], &args[3])?;
+                let input = std::fs::File::open(&args[4])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                let mut output = std::io::BufWriter::new(
+                    std::fs::File::create(&args[5])
+                        .map_err(|_| AppError::OtherError("write output failed"))?,
+                );
+                let mut reader =
+                    stream::RsaDecryptReader::new(std::io::BufReader::new(input), &priv_key);
+                let (t, res) = utils::count_time(|| {
+                    std::io::copy(&mut reader, &mut output).and_then(|_| output.flush())
+                });
+                let status = if res.is_ok() { "ok" } else { "failed" };
+                audit::log("decrypt", Some(priv_key.n()), args[4].as_bytes(), status, t);
+                res.map_err(|e| {
+                    AppError::OtherError(match e.kind() {
+                        std::io::ErrorKind::InvalidData => "Decryption error",
+                        _ => "write output failed",
+                    })
+                })?;
+                println!("{}", t);
+            }
+            // seal [<pubkey>] [--to <pubkey>]... <in> <out>，数字信封：AES-256-GCM 加密数据，
+            // RSA 只为每个收件人各加密一份会话密钥
+            "seal" if args.len() >= 4 => {
+                let (keys, files) = args[2..].split_at(args.len() - 4);
+                let pub_keys = keys
+                    .iter()
+                    .chain(&recipients)
+                    .map(|k| parse_pub_key(format, k))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let input = std::fs::read(&files[0])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                let (t, blob) = utils::count_time(|| envelope::seal(&input, &pub_keys));
+                // 每个收件人各记一条，以便从日志中查到数据封装给了哪些密钥
+                for pub_key in &pub_keys {
+                    audit::log("seal", Some(pub_key.n()), &input, audit_status(&blob), t);
+                }
+                let blob = blob.map_err(|e| AppError::OtherError(e))?;
+                std::fs::write(&files[1], armor::armor("RSA ENVELOPE", &blob))
+                    .map_err(|_| AppError::OtherError("write output failed"))?;
+                println!("{}", t);
+            }
+            // open <pubkey> <privkey> <in> <out>
+            "open" if args.len() >= 6 => {
+                let (_, priv_key) = parse_keys(format, &args[2], &args[3])?;
+                let blob = std::fs::read(&args[4])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                // 也接受旧版本写出的未包装的二进制信封
+                let blob = match std::str::from_utf8(&blob) {
+                    Ok(text) if armor::is_armored(text) => armor::dearmor("RSA ENVELOPE", text)
+                        .map_err(|e| AppError::OtherError(e.message()))?,
+                    _ => blob,
+                };
+                let (t, plain) = utils::count_time(|| envelope::open(&blob, &priv_key));
+                audit::log("open", Some(priv_key.n()), &blob, audit_status(&plain), t);
+                let plain = plain.map_err(|e| AppError::OtherError(e))?;
+                std::fs::write(&args[5], plain)
+                    .map_err(|_| AppError::OtherError("write output failed"))?;
+                println!("{}", t);
+            }
+            // exportkey <pubkey> <privkey> [pkcs1|pkcs8]，将本程序的密钥导出为 openssl 可读取的 PEM
+            "exportkey" if args.len() >= 4 => {
+                let key_format = match args.get(4) {
+                    Some(name) => {
+                        rsa::KeyFormat::from_name(name).map_err(|e| AppError::OtherError(e))?
+                    }
+                    None => rsa::KeyFormat::Pkcs8,
+                };
+                let (pub_key, priv_key) = parse_keys(format, &args[2], &args[3])?;
+                print!("{}", pub_key.to_pem(key_format));
+                print!(
+                    "{}",
+                    priv_key
+                        .to_pem(key_format)
+                        .map_err(|e| AppError::OtherError(e))?
+                );
+            }
+            // sshkey <pubkey> [comment]，输出可写入 authorized_keys 的一行
+            "sshkey" => {
+                let pub_key = parse_pub_key(format, &args[2])?;
+                let comment = args.get(3).map(|c| c.as_str()).unwrap_or("thss-rsa");
+                println!("{}", pub_key.to_openssh(comment));
+            }
+            // exportjwk <pubkey> <privkey>，依次输出公钥与私钥的 JWK
+            "exportjwk" if args.len() >= 4 => {
+                let (pub_key, priv_key) = parse_keys(format, &args[2], &args[3])?;
+                println!("{}\n{}", pub_key.to_jwk(), priv_key.to_jwk());
+            }
+            // importkey <file>，读取 PKCS#1 或 PKCS#8 的 PEM 私钥或公钥、JWK，或 OpenSSH 公钥，输出本程序的格式
+            "importkey" => {
+                let text = std::fs::read_to_string(&args[2])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                if text.trim_start().starts_with('{') {
+                    let jwk = json::parse(&text).map_err(|e| AppError::OtherError(e))?;
+                    if jwk.get("d").is_some() {
+                        let priv_key = rsa::PrivateKey::from_jwk(&text)
+                            .map_err(|e| AppError::OtherError(e))?;
+                        println!(
+                            "{}",
+                            fmt_keys(format, armored, &priv_key.public_key(), &priv_key)?
+                        );
+                    } else {
+                        let pub_key =
+                            rsa::PublicKey::from_jwk(&text).map_err(|e| AppError::OtherError(e))?;
+                        println!(
+                            "{}",
+                            armor_key(
+                                armored,
+                                PUB_KEY_ARMOR,
+                                fmt_pair(format, pub_key.n(), &BigInt::from(pub_key.e()))?
+                            )
+                        );
+                    }
+                } else if text.trim_start().starts_with("ssh-rsa ") {
+                    let pub_key =
+                        rsa::PublicKey::from_openssh(&text).map_err(|e| AppError::OtherError(e))?;
+                    println!(
+                        "{}",
+                        armor_key(
+                            armored,
+                            PUB_KEY_ARMOR,
+                            fmt_pair(format, pub_key.n(), &BigInt::from(pub_key.e()))?
+                        )
+                    );
+                } else if armor::depem(&text)
+                    .map_err(|e| AppError::OtherError(e))?
+                    .0
+                    .ends_with("PRIVATE KEY")
+                {
+                    let priv_key =
+                        rsa::PrivateKey::from_pem(&text).map_err(|e| AppError::OtherError(e))?;
+                    println!(
+                        "{}",
+                        fmt_keys(format, armored, &priv_key.public_key(), &priv_key)?
+                    );
+                } else {
+                    let pub_key =
+                        rsa::PublicKey::from_pem(&text).map_err(|e| AppError::OtherError(e))?;
+                    println!(
+                        "{}",
+                        armor_key(
+                            armored,
+                            PUB_KEY_ARMOR,
+                            fmt_pair(format, pub_key.n(), &BigInt::from(pub_key.e()))?
+                        )
+                    );
+                }
+            }
+            // sign <pubkey> <privkey> <message>，签名按 --format 以十六进制、base64 或 base64url 输出
+            "sign" if args.len() >= 5 => {
+                let (_, priv_key) = parse_keys(format, &args[2], &args[3])?;
+                let encoding = block_encoding(format)?;
+                let (t, sign) = utils::count_time(|| {
+                    rsa::sign_traced(
+                        &args[4],
+                        &priv_key,
+                        encoding,
+                        trace_mode.then_some(&mut trace),
+                    )
+                });
+                audit::log(
+                    "sign",
+                    Some(priv_key.n()),
+                    args[4].as_bytes(),
+                    audit_status(&sign),
+                    t,
+                );
+                let sign = sign.map_err(|e| AppError::OtherError(e))?;
+                println!("{}", armor::armor("RSA SIGNATURE", sign.as_bytes()));
+            }
+            // verify <pubkey> <message> <signature>，签名可为 @文件，文件中可以是文本块
+            "verify" if args.len() >= 5 => {
+                let pub_key = parse_pub_key(format, &args[2])?;
+                let sign = match args[4].strip_prefix('@') {
+                    Some(path) => std::fs::read_to_string(path)
+                        .map_err(|_| AppError::OtherError("read file failed"))?,
+                    None => args[4].clone(),
+                };
+                let sign = if armor::is_armored(&sign) {
+                    armor::dearmor_text("RSA SIGNATURE", &sign)
+                        .map_err(|e| AppError::OtherError(e.message()))?
+                } else {
+                    sign.trim().to_owned()
+                };
+                let (t, report) = utils::count_time(|| {
+                    rsa::ver_sign_traced(
+                        &args[3],
+                        &sign,
+                        &pub_key,
+                        trace_mode.then_some(&mut trace),
+                    )
+                });
+                let status = if report.valid() { "valid" } else { "invalid" };
+                audit::log("verify", Some(pub_key.n()), args[3].as_bytes(), status, t);
+                println!("{}", report.fmt_report());
+                println!("{}", report.valid());
+            }
+            // signdetached <pubkey> <privkey> <file> [pkcs1v15|pss]，输出只含签名的文本块，消息可以是任意文件
+            "signdetached" if args.len() >= 5 => {
+                let (_, priv_key) = parse_keys(format, &args[2], &args[3])?;
+                let scheme = match args.get(5) {
+                    Some(name) => rsa::SignatureScheme::from_name(name)
+                        .map_err(|e| AppError::OtherError(e))?,
+                    None => rsa::SignatureScheme::Pkcs1v15,
+                };
+                let msg = std::fs::read(&args[4])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                let (t, sig) = utils::count_time(|| rsa::sign_detached(&msg, &priv_key, scheme));
+                audit::log("sign", Some(priv_key.n()), &msg, audit_status(&sig), t);
+                let sig = sig.map_err(|e| AppError::OtherError(e))?;
+                println!(
+                    "{}",
+                    armor::armor("RSA DETACHED SIGNATURE", sig.fmt().as_bytes())
+                );
+            }
+            // verifydetached <pubkey> <file> <sigfile>
+            "verifydetached" if args.len() >= 5 => {
+                let pub_key = parse_pub_key(format, &args[2])?;
+                let msg = std::fs::read(&args[3])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                let sig = std::fs::read_to_string(&args[4])
+                    .map_err(|_| AppError::OtherError("read file failed"))?;
+                let sig = armor::dearmor_text("RSA DETACHED SIGNATURE", &sig)
+                    .map_err(|e| AppError::OtherError(e.message()))?;
+                let sig =
+                    rsa::DetachedSignature::parse(&sig).map_err(|e| AppError::OtherError(e))?;
+                if sig.fingerprint != pub_key.fingerprint() {
+                    eprintln!(
+                        "{}",
+                        i18n::tr("Warning: signature was made by a different key")
+                    );
+                }
+                let (t, valid) = utils::count_time(|| rsa::verify_detached(&msg, &sig, &pub_key));
+                let status = if valid { "valid" } else { "invalid" };
+                audit::log("verify", Some(pub_key.n()), &msg, status, t);
+                println!("{}", valid);
+            }
+            // difftest <len> [rounds]，与 RustCrypto 的 rsa crate 逐块比较结果
+            #[cfg(feature = "differential")]
+            "difftest" => {
+                let keylen = parse_usize(&args[2])?;
+                let rounds = match args.get(3) {
+                    Some(r) => parse_usize(r)?,
+                    None => 10,
+                };
+                let divergences =
+                    differential::run(keylen, rounds).map_err(|e| AppError::OtherError(e))?;
+                for d in &divergences {
+                    println!("{}", d.fmt());
+                }
+                println!("{} rounds, {} divergences", rounds, divergences.len());
+                if !divergences.is_empty() {
+                    return Err(AppError::OtherError("implementations diverged"));
+                }
+            }
+            // bignumcmp <len> [rounds]，与 num-bigint 对照大数运算的结果与耗时
+            #[cfg(feature = "num-bigint")]
+            "bignumcmp" => {
+                let keylen = parse_usize(&args[2])?;
+                let rounds = match args.get(3) {
+                    Some(r) => parse_usize(r)?,
+                    None => 10,
+                };
+                let res = bignum::compare(keylen, rounds).map_err(|e| AppError::OtherError(e))?;
+                println!("BigInt: {}us", res.ours);
+                println!("num-bigint: {}us", res.theirs);
+                println!("{} rounds, {} mismatches", rounds, res.mismatches);
+                if res.mismatches > 0 {
+                    return Err(AppError::OtherError("implementations diverged"));
+                }
+            }
+            // audit show，显示审计日志
+            "audit" if args[2] == "show" => {
+                let records =
+                    audit::read().map_err(|_| AppError::OtherError("read audit log failed"))?;
+                println!("{}", audit::render(&records));
+            }
             _ => return Ok(()),
         }
+        if trace_mode {
+            println!("{}", trace::render_table(&trace));
+        }
+        if !bench.times.is_empty() {
+            if let Some(path) = csv_path {
+                bench
+                    .save_csv(&path)
+                    .map_err(|_| AppError::OtherError("write csv failed"))?;
+            }
+            if let Some(path) = svg_path {
+                bench
+                    .save_svg(&path)
+                    .map_err(|_| AppError::OtherError("write svg failed"))?;
+            }
+            if let Some(path) = png_path {
+                bench
+                    .save_png(&path)
+                    .map_err(|_| AppError::OtherError("write png failed"))?;
+            }
+        }
     }
     Ok(())
 }
@@ -0,0 +1,141 @@
+use std::ops;
+
+use crate::algorithms::{BarrettContext, MontgomeryContext};
+use crate::bigint::{BigInt, ONE};
+
+/// 模数及其预计算数据：巴雷特约简的预计算结果，模数为奇数时还有蒙哥马利约简的预计算结果。
+/// 同一模数上的运算都经由它进行
+#[derive(Clone)]
+pub struct Modulus {
+    barrett: BarrettContext,
+    mont: Option<MontgomeryContext>,
+}
+
+impl Modulus {
+    pub fn new(n: &BigInt) -> Result<Self, &'static str> {
+        if *n <= ONE {
+            return Err("Modulus must be greater than 1");
+        }
+        Ok(Self {
+            barrett: BarrettContext::new(n),
+            mont: MontgomeryContext::new(n).ok(),
+        })
+    }
+
+    pub fn n(&self) -> &BigInt {
+        self.barrett.n()
+    }
+
+    pub fn barrett(&self) -> &BarrettContext {
+        &self.barrett
+    }
+
+    /// 模数为奇数时的蒙哥马利约简预计算结果
+    pub fn mont(&self) -> Option<&MontgomeryContext> {
+        self.mont.as_ref()
+    }
+}
+
+/// 附带模数的剩余类，值始终小于模数。运算两边的模数不同时 panic
+#[derive(Clone)]
+pub struct ModInt<'m> {
+    value: BigInt,
+    modulus: &'m Modulus,
+}
+
+impl<'m> ModInt<'m> {
+    /// x mod n
+    pub fn new(x: &BigInt, modulus: &'m Modulus) -> Self {
+        let value = if x < modulus.n() {
+            x.clone()
+        } else {
+            x % modulus.n()
+        };
+        Self { value, modulus }
+    }
+
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    pub fn into_value(self) -> BigInt {
+        self.value
+    }
+
+    /// self^exp，模数为奇数时使用蒙哥马利约简，否则使用巴雷特约简
+    pub fn pow(&self, exp: &BigInt) -> Self {
+        let m = self.modulus;
+        let value = match &m.mont {
+            Some(ctx) => ctx.mod_power(&self.value, exp),
+            None => m.barrett.mod_pow(&self.value, exp),
+        };
+        Self { value, modulus: m }
+    }
+
+    /// 同 `pow`，模数为奇数时使用常数时间的 `MontgomeryContext::mod_power_ct`，用于私钥运算
+    pub fn pow_ct(&self, exp: &BigInt) -> Self {
+        let m = self.modulus;
+        match &m.mont {
+            Some(ctx) => Self {
+                value: ctx.mod_power_ct(&self.value, exp),
+                modulus: m,
+            },
+            None => self.pow(exp),
+        }
+    }
+
+    fn check(&self, rhs: &Self) {
+        assert!(
+            self.modulus.n() == rhs.modulus.n(),
+            "ModInt operands have different moduli"
+        );
+    }
+}
+
+impl PartialEq for ModInt<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.modulus.n() == other.modulus.n() && self.value == other.value
+    }
+}
+
+impl<'m> ops::Add<&ModInt<'m>> for &ModInt<'m> {
+    type Output = ModInt<'m>;
+    fn add(self, rhs: &ModInt<'m>) -> Self::Output {
+        self.check(rhs);
+        let mut value = &self.value + &rhs.value;
+        if value >= *self.modulus.n() {
+            value -= self.modulus.n();
+        }
+        ModInt {
+            value,
+            modulus: self.modulus,
+        }
+    }
+}
+
+impl<'m> ops::Sub<&ModInt<'m>> for &ModInt<'m> {
+    type Output = ModInt<'m>;
+    fn sub(self, rhs: &ModInt<'m>) -> Self::Output {
+        self.check(rhs);
+        let value = match self.value.checked_sub(&rhs.value) {
+            Some(v) => v,
+            None => &(&self.value + self.modulus.n()) - &rhs.value,
+        };
+        ModInt {
+            value,
+            modulus: self.modulus,
+        }
+    }
+}
+
+impl<'m> ops::Mul<&ModInt<'m>> for &ModInt<'m> {
+    type Output = ModInt<'m>;
+    fn mul(self, rhs: &ModInt<'m>) -> Self::Output {
+        self.check(rhs);
+        let m = self.modulus;
+        ModInt {
+            value: m.barrett.reduce(&(&self.value * &rhs.value)),
+            modulus: m,
+        }
+    }
+}
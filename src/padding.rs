@@ -0,0 +1,205 @@
+use crate::hash::{self, SHA256_LEN};
+
+/// PKCS#1 v1.5 签名中可用的摘要算法
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// DER 编码的 DigestInfo 中摘要之前的部分，即 SEQUENCE { AlgorithmIdentifier, OCTET STRING 头 }，
+    /// 取自 RFC 8017 第 9.2 节的注 1
+    pub fn digest_info_prefix(&self) -> &'static [u8] {
+        match self {
+            HashAlgorithm::Sha1 => &[
+                0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04,
+                0x14,
+            ],
+            HashAlgorithm::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+            HashAlgorithm::Sha384 => &[
+                0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x02, 0x05, 0x00, 0x04, 0x30,
+            ],
+            HashAlgorithm::Sha512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x03, 0x05, 0x00, 0x04, 0x40,
+            ],
+        }
+    }
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+}
+
+/// EMSA-PKCS1-v1_5 编码：`00 01 FF..FF 00 DigestInfo`，em_len 为模数的字节数
+pub fn emsa_pkcs1_v15_encode(
+    alg: HashAlgorithm,
+    digest: &[u8],
+    em_len: usize,
+) -> Result<Vec<u8>, &'static str> {
+    if digest.len() != alg.digest_len() {
+        return Err("Digest length does not match hash algorithm");
+    }
+    let prefix = alg.digest_info_prefix();
+    let t_len = prefix.len() + digest.len();
+    // 至少 8 个字节的 FF 填充
+    if em_len < t_len + 11 {
+        return Err("Intended encoded message length too short");
+    }
+
+    let mut em = Vec::with_capacity(em_len);
+    em.extend_from_slice(&[0x00, 0x01]);
+    em.resize(em_len - t_len - 1, 0xff);
+    em.push(0x00);
+    em.extend_from_slice(prefix);
+    em.extend_from_slice(digest);
+    Ok(em)
+}
+
+/// 严格验证：按期望的摘要重新编码后与 em 逐字节比较，而不是解析 em。
+/// 这样填充长度、DigestInfo 中的参数或摘要之后的多余数据都不可能被伪造者利用（Bleichenbacher '06）
+pub fn emsa_pkcs1_v15_verify(alg: HashAlgorithm, digest: &[u8], em: &[u8]) -> bool {
+    match emsa_pkcs1_v15_encode(alg, digest, em.len()) {
+        Ok(expected) => {
+            // 不在第一个不同处提前返回，避免泄露比较位置
+            expected.iter().zip(em).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+        }
+        Err(_) => false,
+    }
+}
+
+/// EME-OAEP 编码（SHA-256 与 MGF1）：`00 || maskedSeed || maskedDB`，其中 DB = lHash || 00..00 || 01 || M，
+/// lHash 为 label 的摘要，k 为模数的字节数，seed 为随机数
+pub fn eme_oaep_encode(
+    msg: &[u8],
+    label: &[u8],
+    seed: &[u8; SHA256_LEN],
+    k: usize,
+) -> Result<Vec<u8>, &'static str> {
+    if k < 2 * SHA256_LEN + 2 || msg.len() > k - 2 * SHA256_LEN - 2 {
+        return Err("Message is too long for the key");
+    }
+    let mut db = hash::sha256(label).to_vec();
+    db.resize(k - msg.len() - SHA256_LEN - 2, 0);
+    db.push(0x01);
+    db.extend_from_slice(msg);
+
+    let db_mask = hash::mgf1_sha256(seed, db.len());
+    let masked_db: Vec<u8> = db.iter().zip(db_mask).map(|(a, b)| a ^ b).collect();
+    let seed_mask = hash::mgf1_sha256(&masked_db, SHA256_LEN);
+
+    let mut em = Vec::with_capacity(k);
+    em.push(0x00);
+    em.extend(seed.iter().zip(seed_mask).map(|(a, b)| a ^ b));
+    em.extend_from_slice(&masked_db);
+    Ok(em)
+}
+
+/// EME-OAEP 解码，em 的长度为模数的字节数。首字节、lHash 与分隔的 01 都检查完后才判断结果，
+/// 失败时统一返回同一个错误，不泄露是哪一项检查失败（Manger '01）
+pub fn eme_oaep_decode(em: &[u8], label: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if em.len() < 2 * SHA256_LEN + 2 {
+        return Err("Decryption error");
+    }
+    let (masked_seed, masked_db) = em[1..].split_at(SHA256_LEN);
+    let seed_mask = hash::mgf1_sha256(masked_db, SHA256_LEN);
+    let seed: Vec<u8> = masked_seed
+        .iter()
+        .zip(seed_mask)
+        .map(|(a, b)| a ^ b)
+        .collect();
+    let db_mask = hash::mgf1_sha256(&seed, masked_db.len());
+    let db: Vec<u8> = masked_db.iter().zip(db_mask).map(|(a, b)| a ^ b).collect();
+
+    let l_hash = hash::sha256(label);
+    let mut bad = em[0] | l_hash.iter().zip(&db).fold(0, |acc, (a, b)| acc | (a ^ b));
+    // 找到 lHash 之后第一个非 0 字节，它必须是 01；扫描整段，不在找到时提前结束
+    let mut index = 0;
+    let mut looking = true;
+    for (i, &b) in db.iter().enumerate().skip(SHA256_LEN) {
+        let found = looking & (b == 0x01);
+        index |= if found { i } else { 0 };
+        bad |= (looking & (b != 0x00) & (b != 0x01)) as u8;
+        looking &= b == 0x00;
+    }
+    bad |= looking as u8;
+    if bad != 0 {
+        return Err("Decryption error");
+    }
+    Ok(db[index + 1..].to_vec())
+}
+
+/// PSS 中 M' = 00 * 8 || mHash || salt 的摘要
+fn pss_hash(m_hash: &[u8], salt: &[u8]) -> [u8; SHA256_LEN] {
+    let mut m = vec![0; 8];
+    m.extend_from_slice(m_hash);
+    m.extend_from_slice(salt);
+    hash::sha256(&m)
+}
+
+/// EMSA-PSS 编码（SHA-256 与 MGF1）：`maskedDB || H || BC`，其中 DB = 00..00 || 01 || salt，
+/// H 为 M' 的摘要。em_bits 为模数位数减 1，编码结果最高的 8 * em_len - em_bits 位为 0
+pub fn emsa_pss_encode(
+    m_hash: &[u8],
+    em_bits: usize,
+    salt: &[u8],
+) -> Result<Vec<u8>, &'static str> {
+    if m_hash.len() != SHA256_LEN {
+        return Err("Digest length does not match hash algorithm");
+    }
+    let em_len = em_bits.div_ceil(8);
+    if em_len < SHA256_LEN + salt.len() + 2 {
+        return Err("Intended encoded message length too short");
+    }
+    let h = pss_hash(m_hash, salt);
+    let mut db = vec![0; em_len - salt.len() - SHA256_LEN - 2];
+    db.push(0x01);
+    db.extend_from_slice(salt);
+    let db_mask = hash::mgf1_sha256(&h, db.len());
+    let mut em: Vec<u8> = db.iter().zip(db_mask).map(|(a, b)| a ^ b).collect();
+    em[0] &= 0xff >> (8 * em_len - em_bits);
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    Ok(em)
+}
+
+/// EMSA-PSS 验证，salt_len 为签名时的盐长度，em 须恰为 ceil(em_bits / 8) 字节
+pub fn emsa_pss_verify(m_hash: &[u8], em: &[u8], em_bits: usize, salt_len: usize) -> bool {
+    let em_len = em_bits.div_ceil(8);
+    if m_hash.len() != SHA256_LEN
+        || em.len() != em_len
+        || em_len < SHA256_LEN + salt_len + 2
+        || em[em_len - 1] != 0xbc
+    {
+        return false;
+    }
+    let top_mask = 0xff >> (8 * em_len - em_bits);
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - SHA256_LEN - 1);
+    if masked_db[0] & !top_mask != 0 {
+        return false;
+    }
+    let db_mask = hash::mgf1_sha256(h, masked_db.len());
+    let mut db: Vec<u8> = masked_db.iter().zip(db_mask).map(|(a, b)| a ^ b).collect();
+    db[0] &= top_mask;
+    let ps_len = em_len - SHA256_LEN - salt_len - 2;
+    if db[..ps_len].iter().any(|b| *b != 0) || db[ps_len] != 0x01 {
+        return false;
+    }
+    let salt = &db[ps_len + 1..];
+    pss_hash(m_hash, salt)
+        .iter()
+        .zip(h)
+        .fold(0, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
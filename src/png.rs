@@ -0,0 +1,199 @@
+/// 调色板图像，每个像素为调色板中的下标，用于把图表导出为 PNG
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    palette: Vec<[u8; 3]>,
+    pixels: Vec<u8>,
+}
+
+/// 3x5 点阵字体，每行 3 位，高位在左；大写字母按小写绘制，没有的字符画作 `?`
+const GLYPHS: &[(char, [u8; 5])] = &[
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('a', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('b', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('c', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('d', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('e', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('f', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('g', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('h', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('i', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('j', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('k', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('l', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('m', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('n', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('o', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('p', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('r', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('s', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('t', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('u', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('v', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('w', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('x', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    ('?', [0b111, 0b001, 0b010, 0b000, 0b010]),
+];
+
+/// 字形放大的倍数及字符间的空隙，单位为像素
+const FONT_SCALE: usize = 2;
+const FONT_GAP: usize = 2;
+const FONT_HEIGHT: usize = 5 * FONT_SCALE;
+
+fn glyph(c: char) -> [u8; 5] {
+    let c = c.to_ascii_lowercase();
+    GLYPHS
+        .iter()
+        .find(|(g, _)| *g == c)
+        .or_else(|| GLYPHS.iter().find(|(g, _)| *g == '?'))
+        .map(|(_, rows)| *rows)
+        .unwrap_or_default()
+}
+
+impl Canvas {
+    /// 以 palette[0] 为背景色
+    pub fn new(width: usize, height: usize, palette: &[[u8; 3]]) -> Self {
+        Self {
+            width,
+            height,
+            palette: palette.to_vec(),
+            pixels: vec![0; width * height],
+        }
+    }
+
+    /// 超出画布的部分被裁掉
+    pub fn fill_rect(&mut self, x: i64, y: i64, w: i64, h: i64, color: u8) {
+        let clamp = |v: i64, max: usize| v.clamp(0, max as i64) as usize;
+        let (x0, x1) = (clamp(x, self.width), clamp(x + w, self.width));
+        let (y0, y1) = (clamp(y, self.height), clamp(y + h, self.height));
+        for row in y0..y1 {
+            self.pixels[row * self.width + x0..row * self.width + x1].fill(color);
+        }
+    }
+
+    pub fn text_width(s: &str) -> i64 {
+        let n = s.chars().count();
+        (n * (3 * FONT_SCALE + FONT_GAP)).saturating_sub(FONT_GAP) as i64
+    }
+
+    /// 与 SVG 的 text 相同，y 为基线
+    pub fn text(&mut self, x: i64, y: i64, s: &str, color: u8) {
+        let top = y - FONT_HEIGHT as i64;
+        let scale = FONT_SCALE as i64;
+        for (i, c) in s.chars().enumerate() {
+            let left = x + i as i64 * (3 * scale + FONT_GAP as i64);
+            for (r, bits) in glyph(c).iter().enumerate() {
+                for col in 0..3 {
+                    if bits >> (2 - col) & 1 == 1 {
+                        self.fill_rect(
+                            left + col * scale,
+                            top + r as i64 * scale,
+                            scale,
+                            scale,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// 8 位调色板 PNG（RFC 2083），zlib 流只使用不压缩的存储块
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            // 每行前的过滤类型，0 表示不过滤
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut ihdr = vec![];
+        ihdr.extend_from_slice(&(self.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 位深 8、调色板图像、deflate、标准过滤、不交错
+        ihdr.extend_from_slice(&[8, 3, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut png, b"IHDR", &ihdr);
+        write_chunk(&mut png, b"PLTE", &self.palette.concat());
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// zlib（RFC 1950）包装的 deflate 存储块，每块至多 65535 字节
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// PNG 与 zlib 使用的 CRC-32（多项式 0xedb88320）
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for &d in data {
+        a = (a + d as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
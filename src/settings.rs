@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::utils;
+
+/// 每种密钥长度保留的最近计时次数
+const KEEP_TIMES: usize = 10;
+
+/// 持久化的设置
+#[derive(Default)]
+pub struct Settings {
+    /// 密钥长度 -> 耗时，单位 us，用于估计生成时间
+    keygen_times: BTreeMap<usize, Vec<u128>>,
+    /// 是否混入 CPU 硬件随机数
+    pub hardware_rng: bool,
+    /// 生成素数时的 Miller-Rabin 轮数，None 为按位数自动选择
+    pub mr_rounds: Option<usize>,
+}
+
+fn path() -> PathBuf {
+    utils::home_file(".thss-rsa-settings")
+}
+
+impl Settings {
+    /// 每行形如 `keygen <len> <us>`、`hardware_rng on` 或 `mr_rounds <n>`，无法识别的行被忽略
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+        let Ok(s) = fs::read_to_string(path()) else {
+            return settings;
+        };
+        for line in s.lines() {
+            let mut sp = line.split_whitespace();
+            match sp.next() {
+                Some("keygen") => {
+                    if let (Some(Ok(len)), Some(Ok(t))) = (
+                        sp.next().map(str::parse::<usize>),
+                        sp.next().map(str::parse::<u128>),
+                    ) {
+                        settings.record_keygen(len, t);
+                    }
+                }
+                Some("hardware_rng") => settings.hardware_rng = sp.next() == Some("on"),
+                Some("mr_rounds") => {
+                    settings.mr_rounds = sp.next().and_then(|r| r.parse().ok()).filter(|r| *r > 0)
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut lines = vec![format!(
+            "hardware_rng {}",
+            if self.hardware_rng { "on" } else { "off" }
+        )];
+        if let Some(r) = self.mr_rounds {
+            lines.push(format!("mr_rounds {}", r));
+        }
+        for (len, times) in &self.keygen_times {
+            for t in times {
+                lines.push(format!("keygen {} {}", len, t));
+            }
+        }
+        fs::write(path(), lines.join("\n") + "\n")
+    }
+
+    pub fn record_keygen(&mut self, len: usize, t: u128) {
+        let times = self.keygen_times.entry(len).or_default();
+        times.push(t);
+        if times.len() > KEEP_TIMES {
+            times.remove(0);
+        }
+    }
+
+    /// 估计生成 len 位密钥的耗时 (us)。没有该长度的记录时，由最接近的长度按 len^4 外推：
+    /// 素数密度与位数成反比，每次 Miller-Rabin 约为位数的立方
+    pub fn estimate_keygen(&self, len: usize) -> Option<u128> {
+        let (&l, times) = self
+            .keygen_times
+            .iter()
+            .min_by_key(|(l, _)| l.abs_diff(len))?;
+        let mean = times.iter().sum::<u128>() as f64 / times.len() as f64;
+        Some((mean * (len as f64 / l as f64).powi(4)) as u128)
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::{self, Read, Write};
+
+use crate::rsa::{self, PrivateKey, PublicKey};
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// 逐块加密写入的数据并写到 inner，内存中只保留不满一块的明文，密文格式与 `rsa::encrypt_bytes` 相同。
+/// 写完后须调用 `finish` 写出带填充的最后一块，否则密文不完整
+pub struct RsaEncryptWriter<W: Write> {
+    inner: W,
+    key: PublicKey,
+    buf: Vec<u8>,
+    block_bytes: usize,
+}
+
+impl<W: Write> RsaEncryptWriter<W> {
+    pub fn new(inner: W, key: &PublicKey) -> Self {
+        let block_bytes = rsa::block_bytes(key.modulus());
+        Self {
+            inner,
+            key: key.clone(),
+            buf: Vec::with_capacity(block_bytes),
+            block_bytes,
+        }
+    }
+
+    /// 补上 0x80 后写出最后一块，返回 inner
+    pub fn finish(mut self) -> io::Result<W> {
+        self.buf.push(0x80);
+        // 明文恰为整块时 0x80 单独成为最后一块
+        for block in self.buf.chunks(self.block_bytes) {
+            self.inner
+                .write_all(&rsa::encrypt_block(block, &self.key))?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for RsaEncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        let full = self.buf.len() / self.block_bytes * self.block_bytes;
+        for block in self.buf[..full].chunks(self.block_bytes) {
+            self.inner
+                .write_all(&rsa::encrypt_block(block, &self.key))?;
+        }
+        self.buf.drain(..full);
+        Ok(data.len())
+    }
+
+    /// 只刷新 inner，不满一块的明文要等到 `finish` 才写出
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 从 inner 逐块读出并解密密文。最后一块要读到末尾才能确定并去掉填充，
+/// 因此总是先解密出下一块，再交出前一块的明文。密文有误时返回 `InvalidData` 错误
+pub struct RsaDecryptReader<R: Read> {
+    inner: R,
+    key: PrivateKey,
+    cipher: Vec<u8>,
+    /// 已解密但可能是最后一块、尚未交出的明文块
+    held: Option<Vec<u8>>,
+    out: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> RsaDecryptReader<R> {
+    pub fn new(inner: R, key: &PrivateKey) -> Self {
+        Self {
+            inner,
+            key: key.clone(),
+            cipher: vec![0; rsa::modulus_bytes(key.modulus())],
+            held: None,
+            out: vec![],
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// 读满一个密文块，到达末尾时为 false，只读到半块时出错
+    fn read_block(&mut self) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < self.cipher.len() {
+            match self.inner.read(&mut self.cipher[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        match filled {
+            0 => Ok(false),
+            n if n == self.cipher.len() => Ok(true),
+            _ => Err(invalid_data("Decryption error")),
+        }
+    }
+
+    /// 准备好下一段可交出的明文，到达末尾时 out 为空
+    fn refill(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.pos = 0;
+        while self.out.is_empty() && !self.done {
+            if self.read_block()? {
+                let block = rsa::decrypt_block(&self.cipher, &self.key).map_err(invalid_data)?;
+                if let Some(prev) = self.held.replace(block) {
+                    self.out = prev;
+                }
+            } else {
+                self.done = true;
+                let last = self
+                    .held
+                    .take()
+                    .ok_or_else(|| invalid_data("Decryption error"))?;
+                self.out = rsa::strip_padding(last).map_err(invalid_data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for RsaDecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.out.len() {
+            self.refill()?;
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
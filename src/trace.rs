@@ -0,0 +1,79 @@
+use std::cmp;
+
+/// 运算过程中记录下的一个中间步骤
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub name: String,
+    pub value: String,
+}
+
+pub type Trace = Vec<Step>;
+
+/// 接收步骤的回调。`Trace` 会收集全部步骤，任意 `FnMut(Step)` 闭包也可直接作为回调使用
+pub trait Tracer {
+    fn step(&mut self, step: Step);
+}
+
+impl Tracer for Trace {
+    fn step(&mut self, step: Step) {
+        self.push(step);
+    }
+}
+
+impl<F: FnMut(Step)> Tracer for F {
+    fn step(&mut self, step: Step) {
+        self(step);
+    }
+}
+
+/// 若启用了记录，则追加一个步骤。step 只在需要记录时才会被求值，避免无谓的格式化开销
+pub fn record<F>(trace: &mut Option<&mut dyn Tracer>, step: F)
+where
+    F: FnOnce() -> (String, String),
+{
+    if let Some(t) = trace {
+        let (name, value) = step();
+        t.step(Step { name, value });
+    }
+}
+
+/// 每个步骤一行，形如 `name = value`
+pub fn fmt_trace(trace: &Trace) -> String {
+    trace
+        .iter()
+        .map(|s| format!("{} = {}", s.name, s.value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 渲染为带序号的文本表格，便于逐步对照算法
+pub fn render_table(trace: &Trace) -> String {
+    let idx_width = cmp::max(1, format!("{}", trace.len()).len());
+    let name_width = trace
+        .iter()
+        .map(|s| s.name.chars().count())
+        .fold("step".len(), cmp::max);
+    let pad = |s: &str, width: usize| format!("{}{}", s, " ".repeat(width - s.chars().count()));
+
+    let mut lines = vec![
+        format!(
+            "{} | {} | value",
+            pad("#", idx_width),
+            pad("step", name_width)
+        ),
+        format!(
+            "{}-+-{}-+------",
+            "-".repeat(idx_width),
+            "-".repeat(name_width)
+        ),
+    ];
+    for (i, s) in trace.iter().enumerate() {
+        lines.push(format!(
+            "{} | {} | {}",
+            pad(&format!("{}", i + 1), idx_width),
+            pad(&s.name, name_width),
+            s.value
+        ));
+    }
+    lines.join("\n")
+}
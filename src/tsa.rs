@@ -0,0 +1,139 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::rsa::{self, PrivateKey, PublicKey};
+use crate::utils;
+
+/// 时间戳令牌：TSA 对 (时间, 文档签名) 的签名
+#[derive(Clone, Debug)]
+pub struct TimestampToken {
+    /// UNIX 时间，单位秒
+    pub time: u64,
+    pub signature: String,
+}
+
+impl TimestampToken {
+    /// 形如 `time:signature`，time 为 16 位十六进制
+    pub fn fmt(&self) -> String {
+        format!("{:016x}:{}", self.time, self.signature)
+    }
+    pub fn parse(s: &str) -> Result<Self, &'static str> {
+        let (st, sig) = s.split_once(":").ok_or("Error parsing timestamp token")?;
+        let time = u64::from_str_radix(st, 16).map_err(|_| "Error parsing timestamp")?;
+        Ok(Self {
+            time,
+            signature: sig.to_owned(),
+        })
+    }
+}
+
+/// 被 TSA 签名的内容，将时间与文档签名绑定在一起
+fn payload(time: u64, doc_signature: &str) -> String {
+    format!("{:016x}:{}", time, doc_signature)
+}
+
+/// 保存 TSA 密钥的文件，第一行为公钥，第二行为私钥
+fn path() -> PathBuf {
+    utils::home_file(".thss-rsa-tsa")
+}
+
+/// 本地时间戳服务，持有一对专用于签发时间戳的密钥
+#[derive(Clone)]
+pub struct Tsa {
+    pub pub_key: PublicKey,
+    key: PrivateKey,
+}
+
+impl Tsa {
+    pub fn new(key_len: usize) -> Result<Self, &'static str> {
+        let (pub_key, key) = rsa::gen_keys(key_len)?;
+        Ok(Self { pub_key, key })
+    }
+
+    pub fn from_keys(pub_key: PublicKey, key: PrivateKey) -> Self {
+        Self { pub_key, key }
+    }
+
+    /// 读取保存的 TSA 密钥，还没有保存过时为 None
+    pub fn load() -> Result<Option<Self>, &'static str> {
+        let Ok(s) = fs::read_to_string(path()) else {
+            return Ok(None);
+        };
+        let mut lines = s.lines();
+        let (Some(pub_key), Some(key)) = (lines.next(), lines.next()) else {
+            return Err("Error parsing TSA key file");
+        };
+        let (pub_key, key) = rsa::key_from_str(pub_key, key)?;
+        Ok(Some(Self { pub_key, key }))
+    }
+
+    /// 文件中有私钥，只允许当前用户读写：unix 下以 0600 创建，已有的文件也先改为 0600 再写入
+    pub fn save(&self) -> io::Result<()> {
+        let (pub_key, key) = rsa::fmt_key(&self.pub_key, &self.key);
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path())?;
+        #[cfg(unix)]
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(format!("{}\n{}\n", pub_key, key).as_bytes())
+    }
+
+    /// 读取保存的 TSA 密钥，没有时生成一对并保存，重启后仍能验证以前签发的时间戳
+    pub fn load_or_new(key_len: usize) -> Result<Self, &'static str> {
+        if let Some(tsa) = Self::load()? {
+            return Ok(tsa);
+        }
+        let tsa = Self::new(key_len)?;
+        tsa.save().map_err(|_| "Error saving TSA key")?;
+        Ok(tsa)
+    }
+
+    /// 为一个文档签名签发当前时间的时间戳
+    pub fn stamp(&self, doc_signature: &str) -> Result<TimestampToken, &'static str> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let signature = self.key.sign(&payload(time, doc_signature))?;
+        Ok(TimestampToken { time, signature })
+    }
+
+    pub fn pub_key(&self) -> String {
+        rsa::fmt_key(&self.pub_key, &self.key).0
+    }
+}
+
+/// 用 TSA 公钥检查令牌确实是针对这个文档签名签发的
+pub fn verify_token(token: &TimestampToken, doc_signature: &str, tsa_key: &PublicKey) -> bool {
+    tsa_key.verify(&payload(token.time, doc_signature), &token.signature)
+}
+
+/// 对文档签名并附上时间戳，返回 (文档签名, 时间戳令牌)
+pub fn sign_timestamped(
+    message: &str,
+    key: &PrivateKey,
+    tsa: &Tsa,
+) -> Result<(String, TimestampToken), &'static str> {
+    let signature = key.sign(message)?;
+    let token = tsa.stamp(&signature)?;
+    Ok((signature, token))
+}
+
+/// 同时验证文档签名与时间戳令牌，返回 (文档签名是否有效, 令牌是否有效)
+pub fn ver_sign_timestamped(
+    message: &str,
+    signature: &str,
+    token: &TimestampToken,
+    key: &PublicKey,
+    tsa_key: &PublicKey,
+) -> (bool, bool) {
+    let doc_ok = key.verify(message, signature);
+    let token_ok = verify_token(token, signature, tsa_key);
+    (doc_ok, token_ok)
+}